@@ -1,25 +1,397 @@
 mod lines;
 pub mod shape;
 pub mod processing;
+pub mod pose;
+pub mod scalar;
+pub mod trace;
+pub mod svg;
+#[cfg(feature = "tiled")]
+pub mod tiled;
 
 #[cfg(test)]
 mod tests {
     #[test]
     fn resolution() {
-        use amethyst::core::math::Point2;
+        use nalgebra::Point2;
         use crate::shape::Shape;
-        let a = Shape::new(&[Point2::new(0.0, 0.0), Point2::new(0.0, 10.0), Point2::new(10.0, 10.0), Point2::new(10.0, 0.0)]);
-        let b = Shape::new(&[Point2::new(7.4, 7.5), Point2::new(7.4, 17.5), Point2::new(17.4, 17.5), Point2::new(17.4, 7.5)]);
+        let a = Shape::new([Point2::new(0.0, 0.0), Point2::new(0.0, 10.0), Point2::new(10.0, 10.0), Point2::new(10.0, 0.0)]);
+        let b = Shape::new([Point2::new(7.4, 7.5), Point2::new(7.4, 17.5), Point2::new(17.4, 17.5), Point2::new(17.4, 7.5)]);
         assert!(b.collide(&a).is_some());
     }
     #[test]
+    fn narrow_phase() {
+        use nalgebra::Point2;
+        use crate::shape::Shape;
+        let square = |x: f32, y: f32, size: f32| Shape::new([Point2::new(x, y), Point2::new(x + size, y), Point2::new(x + size, y + size), Point2::new(x, y + size)]);
+        let outer = square(0.0, 0.0, 10.0);
+        // The samples of each shape are tested against the other one, never against themselves.
+        assert!(outer.collide(&square(4.0, 4.0, 2.0)).is_some());
+        assert!(square(4.0, 4.0, 2.0).collide(&outer).is_some());
+        assert!(outer.collide(&square(20.0, 20.0, 2.0)).is_none());
+        assert!(square(20.0, 20.0, 2.0).collide(&outer).is_none());
+    }
+    #[test]
+    fn touch_needs_every_side() {
+        use nalgebra::Point2;
+        use crate::shape::Shape;
+        let outer = Shape::new([Point2::new(0.0f32, 0.0), Point2::new(10.0, 0.0), Point2::new(10.0, 10.0), Point2::new(0.0, 10.0)]);
+        // A vertex on the extension of an edge is outside, even though it touches that edge's line.
+        let beside = Shape::new([Point2::new(15.0f32, 0.0), Point2::new(20.0, 0.0), Point2::new(20.0, -5.0)]);
+        assert!(outer.collide(&beside).is_none());
+        assert!(beside.collide(&outer).is_none());
+        // On the outline itself it does touch.
+        let above = Shape::new([Point2::new(5.0f32, 10.0), Point2::new(8.0, 15.0), Point2::new(2.0, 15.0)]);
+        assert!(matches!(outer.collide(&above), Some(crate::shape::CollisionVector::Touch(_))));
+    }
+    #[test]
+    fn centroid() {
+        use nalgebra::Point2;
+        use crate::shape::Shape;
+        let square = Shape::new([Point2::new(0.0f32, 0.0), Point2::new(10.0, 0.0), Point2::new(10.0, 10.0), Point2::new(0.0, 10.0)]);
+        assert_eq!(square.centroid(), Point2::new(5.0, 5.0));
+        let triangle = Shape::new([Point2::new(0.0f32, 0.0), Point2::new(6.0, 0.0), Point2::new(0.0, 3.0)]);
+        assert_eq!(triangle.centroid(), Point2::new(2.0, 1.0));
+    }
+    #[test]
+    fn flipped_resolution() {
+        use nalgebra::{Point2, Vector2};
+        use crate::shape::{CollisionVector, Shape};
+        assert!(matches!(CollisionVector::Resolve(Vector2::new(1.0f32, -2.0)).flip(), CollisionVector::Resolve(v) if v == Vector2::new(-1.0, 2.0)));
+        assert!(matches!(CollisionVector::Touch(Vector2::new(1.0f32, 0.0)).flip(), CollisionVector::Touch(v) if v == Vector2::new(-1.0, 0.0)));
+        // Only the vertices of the second shape are inside the first, so the result comes from a flip.
+        let outer = Shape::rect(10.0, 10.0);
+        let inner = Shape::new([Point2::new(4.0f32, 4.0), Point2::new(6.0, 4.0), Point2::new(6.0, 6.0), Point2::new(4.0, 6.0)]);
+        assert!(matches!(outer.collide(&inner), Some(CollisionVector::Resolve(_))));
+    }
+    #[test]
+    fn far_from_origin() {
+        use nalgebra::Point2;
+        use crate::shape::GenericShape;
+        let far = 1.0e8_f64;
+        let square = |x: f64| GenericShape::new([Point2::new(x, far), Point2::new(x, far + 1.0), Point2::new(x + 1.0, far + 1.0), Point2::new(x + 1.0, far)]);
+        let a = square(far);
+        let b = square(far + 0.75);
+        let c = square(far + 1.25);
+        assert!(a.collide(&b).is_some());
+        assert!(a.collide(&c).is_none());
+    }
+    #[test]
+    fn resting_contact() {
+        use nalgebra::Point2;
+        use crate::shape::{CollisionVector, Shape};
+        use crate::processing::world::{CollisionWorld, ALL_LAYERS};
+        let rect = |x: f32, y: f32, w: f32, h: f32| Shape::new([Point2::new(x, y), Point2::new(x, y + h), Point2::new(x + w, y + h), Point2::new(x + w, y)]);
+        let floor = rect(0.0, 0.0, 10.0, 10.0);
+        for &gap in &[1.0e-4, 0.0, -1.0e-4] {
+            let block = rect(2.0, 10.0 + gap, 6.0, 6.0);
+            assert!(matches!(block.collide_with_tolerance(&floor, 1.0e-3), Some(CollisionVector::Touch(_))));
+        }
+        assert!(rect(2.0, 10.0001, 6.0, 6.0).collide(&floor).is_none());
+        // One corner sunk 2 units deep and another within the tolerance of an edge: the deep one wins.
+        let sunk = Shape::new([Point2::new(4.0f32, 8.0), Point2::new(9.9995, 9.0), Point2::new(6.0, 16.0)]);
+        for res in [sunk.collide(&floor), sunk.collide_with_tolerance(&floor, 1.0e-3)] {
+            match res {
+                Some(CollisionVector::Resolve(v)) => assert!((v - nalgebra::Vector2::new(0.0, 2.0)).norm() < 1.0e-4, "{:?}", v),
+                other => panic!("expected a resolution, got {:?}", other),
+            }
+        }
+        let mut world = CollisionWorld::new();
+        world.insert(1, floor, ALL_LAYERS);
+        world.set_tolerance(1.0e-3);
+        assert_eq!(world.overlap(&rect(2.0, 10.0001, 6.0, 6.0), ALL_LAYERS).len(), 1);
+    }
+    #[test]
+    fn shape_validation() {
+        use nalgebra::Point2;
+        use crate::shape::{Shape, ShapeError};
+        let shape = |points: &[(f32, f32)]| Shape::try_new(points.iter().map(|&(x, y)| Point2::new(x, y)));
+        assert!(shape(&[(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0)]).is_ok());
+        assert!(shape(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]).is_ok());
+        assert_eq!(shape(&[(0.0, 0.0), (1.0, 1.0)]).unwrap_err(), ShapeError::TooFewPoints(2));
+        assert_eq!(shape(&[(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (0.0, 1.0)]).unwrap_err(), ShapeError::DuplicatePoint(3));
+        assert_eq!(shape(&[(0.0, 0.0), (1.0, 1.0), (2.0, 2.0)]).unwrap_err(), ShapeError::DegenerateArea);
+        assert_eq!(shape(&[(0.0, 0.0), (1.0, 1.0), (1.0, 0.0), (0.0, 1.0)]).unwrap_err(), ShapeError::SelfIntersection);
+        assert_eq!(shape(&[(0.0, 0.0), (0.0, 2.0), (1.0, 1.0), (2.0, 2.0), (2.0, 0.0)]).unwrap_err(), ShapeError::NonConvex);
+    }
+    #[test]
+    fn winding_and_hull() {
+        use nalgebra::Point2;
+        use crate::shape::{Shape, ShapeError};
+        let clockwise = Shape::new([Point2::new(0.0, 0.0), Point2::new(0.0, 10.0), Point2::new(10.0, 10.0), Point2::new(10.0, 0.0)]);
+        let counter_clockwise = Shape::new([Point2::new(0.0, 0.0), Point2::new(10.0, 0.0), Point2::new(10.0, 10.0), Point2::new(0.0, 10.0)]);
+        assert_eq!(clockwise, counter_clockwise);
+        let reordered = [Point2::new(0.0, 0.0), Point2::new(10.0, 0.0), Point2::new(10.0, 10.0), Point2::new(0.0, 10.0)];
+        assert_eq!(clockwise.iter_points().cloned().collect::<Vec<_>>(), reordered.to_vec());
+        let cloud = [(3.0, 4.0), (10.0, 0.0), (0.0, 0.0), (5.0, 0.0), (10.0, 10.0), (2.0, 8.0), (0.0, 10.0), (10.0, 10.0)];
+        let hull = Shape::convex_hull(cloud.iter().map(|&(x, y)| Point2::new(x, y))).unwrap();
+        assert_eq!(hull, counter_clockwise);
+        let line = [Point2::new(0.0f32, 0.0), Point2::new(1.0, 1.0), Point2::new(2.0, 2.0)];
+        assert_eq!(Shape::convex_hull(line).unwrap_err(), ShapeError::DegenerateArea);
+    }
+    #[test]
+    fn convex_decomposition() {
+        use nalgebra::Point2;
+        use crate::shape::Shape;
+        let ell = [(0.0, 0.0), (20.0, 0.0), (20.0, 10.0), (10.0, 10.0), (10.0, 20.0), (0.0, 20.0)];
+        let pieces = Shape::convex_decomposition(ell.iter().rev().map(|&(x, y)| Point2::new(x, y)));
+        assert_eq!(pieces.len(), 2);
+        let covered = |x: f32, y: f32| pieces.iter().any(|piece| piece.contains(Point2::new(x, y)));
+        assert!(covered(15.0, 5.0) && covered(5.0, 15.0) && covered(8.0, 2.0) && covered(2.0, 8.0));
+        assert!(!covered(15.0, 15.0));
+        assert_eq!(Shape::convex_decomposition(Shape::rect(4.0, 4.0).iter_points()), vec![Shape::rect(4.0, 4.0)]);
+    }
+    #[test]
+    fn authored_points() {
+        use nalgebra::Point2;
+        use crate::shape::Shape;
+        let points = [Point2::new(0.0f32, 0.0), Point2::new(10.0, 0.0), Point2::new(10.0, 10.0), Point2::new(0.0, 10.0)];
+        assert_eq!(Shape::new(points).iter_points().cloned().collect::<Vec<_>>(), points.to_vec());
+        let wide = Shape::new([Point2::new(-10.0f32, 2.0), Point2::new(20.0, 2.0), Point2::new(20.0, 8.0), Point2::new(-10.0, 8.0)]);
+        assert!(wide.collide(&Shape::new(points)).is_some());
+    }
+    #[test]
+    fn primitives() {
+        use nalgebra::Point2;
+        use crate::shape::{Aabb, Shape};
+        let close = |a: Aabb, b: Aabb| (a.min - b.min).norm() < 1e-4 && (a.max - b.max).norm() < 1e-4;
+        assert_eq!(Shape::rect(32.0, 16.0).aabb(), Aabb::new(Point2::new(0.0, 0.0), Point2::new(32.0, 16.0)));
+        assert_eq!(Shape::rect_centered(32.0, 16.0).aabb(), Aabb::new(Point2::new(-16.0, -8.0), Point2::new(16.0, 8.0)));
+        assert_eq!(Shape::regular_polygon(6, 5.0).iter_points().count(), 6);
+        assert!(close(Shape::ellipse(4.0, 2.0, 16).aabb(), Aabb::new(Point2::new(-4.0, -2.0), Point2::new(4.0, 2.0))));
+        let rounded = Shape::rounded_rect(10.0, 6.0, 2.0, 4);
+        assert!(close(rounded.aabb(), Aabb::new(Point2::new(-5.0, -3.0), Point2::new(5.0, 3.0))));
+        assert!(!rounded.contains(Point2::new(4.9, 2.9)));
+        assert!(Shape::try_new(rounded.iter_points()).is_ok());
+    }
+    #[test]
+    fn simplification() {
+        use nalgebra::{Point2, Vector2};
+        use crate::pose::Pose;
+        use crate::shape::{ramer_douglas_peucker, visvalingam_whyatt, Shape};
+        let wobble = |i: usize| if i.is_multiple_of(2) { 0.01 } else { -0.01 };
+        let mut outline: Vec<Point2<f32>> = (0..10).map(|i| Point2::new(i as f32, wobble(i)))
+            .chain((0..10).map(|i| Point2::new(10.0 + wobble(i), i as f32)))
+            .chain((0..10).map(|i| Point2::new(10.0 - i as f32, 10.0 + wobble(i))))
+            .chain((0..10).map(|i| Point2::new(wobble(i), 10.0 - i as f32)))
+            .collect();
+        outline.rotate_left(5);
+        assert_eq!(ramer_douglas_peucker(&outline, 0.1).len(), 4);
+        assert_eq!(visvalingam_whyatt(&outline, 0.5).len(), 4);
+        let line = [Point2::new(0.0f32, 0.0), Point2::new(1.0, 0.01), Point2::new(2.0, 0.0), Point2::new(3.0, 0.01)];
+        assert_eq!(ramer_douglas_peucker(&line, 1.0).len(), 3);
+        let mut circle = Shape::ellipse(10.0, 10.0, 64);
+        circle.set_pose(&Pose::from_position(Vector2::new(100.0, 0.0)));
+        for simple in [circle.ramer_douglas_peucker(0.5), circle.visvalingam_whyatt(1.0)] {
+            assert!(simple.iter_points().count() < 64 && simple.iter_points().count() >= 3);
+            assert!(Shape::try_new(simple.iter_points()).is_ok());
+            assert!(simple.contains(Point2::new(100.0, 0.0)) && !simple.contains(Point2::new(0.0, 0.0)));
+        }
+    }
+    #[test]
+    fn sprite_tracing() {
+        use nalgebra::Point2;
+        use crate::shape::Shape;
+        use crate::trace::SpriteTracer;
+        let (width, height) = (32, 16);
+        let mut sheet = vec![0u8; width * height * 4];
+        let mut paint = |x0: usize, y0: usize, x1: usize, y1: usize| {
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    sheet[(y * width + x) * 4 + 3] = 255;
+                }
+            }
+        };
+        paint(0, 0, 12, 4);
+        paint(0, 4, 4, 12);
+        paint(20, 4, 28, 12);
+        let tracer = SpriteTracer::new();
+        let ell: Vec<Shape> = tracer.trace_sprite(&sheet, width, (0, 0, 16, 16));
+        assert!(ell.len() >= 2);
+        let covered = |x: f32, y: f32| ell.iter().any(|piece| piece.contains(Point2::new(x, y)));
+        assert!(covered(10.0, 2.0) && covered(2.0, 10.0) && covered(2.0, 2.0));
+        assert!(!covered(10.0, 10.0) && !covered(13.0, 2.0));
+        let square: Vec<Shape> = SpriteTracer { centered: true, ..SpriteTracer::new() }.trace_sprite(&sheet, width, (16, 0, 16, 16));
+        assert_eq!(square.len(), 1);
+        let bounds = square[0].aabb();
+        assert_eq!((bounds.min, bounds.max), (Point2::new(-4.0, -4.0), Point2::new(4.0, 4.0)));
+        assert_eq!(tracer.trace::<f32>(&sheet, width, height).len(), ell.len() + 1);
+    }
+    #[test]
+    #[cfg(feature = "amethyst")]
+    fn prefab_formats() {
+        use crate::shape::{Shape, ShapeError, ShapeKind, ShapePrefab};
+        let legacy: ShapePrefab = ron::de::from_str("(points: [[0, 0], [0, 10], [10, 10], [10, 0]])").unwrap();
+        assert_eq!(legacy.build().unwrap(), Shape::rect(10.0, 10.0));
+        let rect: ShapePrefab = ron::de::from_str("(shape: Rect(width: 32, height: 16))").unwrap();
+        assert_eq!(rect.build().unwrap(), Shape::rect(32.0, 16.0));
+        let saved = ron::ser::to_string(&rect).unwrap();
+        assert_eq!(ron::de::from_str::<ShapePrefab>(&saved).unwrap().build().unwrap(), Shape::rect(32.0, 16.0));
+        let broken: ShapePrefab = ron::de::from_str("(points: [[0, 0], [1, 1]])").unwrap();
+        assert!(broken.build().is_err());
+        let trigger: ShapePrefab = ron::de::from_str("(shape: Circle(radius: 8, segments: 12), offset: [0, 4], layers: 2, sensor: true)").unwrap();
+        assert_eq!((trigger.layers, trigger.sensor), (2, true));
+        let center = trigger.build().unwrap().aabb();
+        assert!((center.min.y + 4.0).abs() < 1e-4 && (center.max.y - 12.0).abs() < 1e-4);
+        let kind = |text: &str| ron::de::from_str::<ShapeKind>(text).unwrap().build();
+        assert_eq!(kind("Rect(width: 0, height: 16)").unwrap_err(), ShapeError::InvalidSize);
+        assert_eq!(kind("Circle(radius: -8, segments: 12)").unwrap_err(), ShapeError::InvalidSize);
+        assert_eq!(kind("RegularPolygon(sides: 2, radius: 8)").unwrap_err(), ShapeError::TooFewPoints(2));
+        assert_eq!(kind("RoundedRect(width: 10, height: 6, radius: -1, segments: 4)").unwrap_err(), ShapeError::InvalidSize);
+        assert!(kind("RoundedRect(width: 10, height: 6, radius: 0, segments: 4)").is_ok());
+    }
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_round_trip() {
+        use nalgebra::{Point2, Vector2};
+        use crate::pose::Pose;
+        use crate::shape::{CollisionVector, Shape};
+        use crate::scalar::{Fixed, Real};
+        let authored = Shape::rect(10.0, 10.0);
+        let mut moved = authored.clone();
+        moved.set_pose(&Pose::from_position(Vector2::new(50.0, 0.0)));
+        let saved = ron::ser::to_string(&moved).unwrap();
+        assert_eq!(saved, "(points:[[0,0,],[10,0,],[10,10,],[0,10,],],)");
+        let loaded: Shape = ron::de::from_str(&saved).unwrap();
+        assert_eq!(loaded, authored);
+        assert_eq!(loaded.centroid(), Point2::new(5.0, 5.0));
+        let vector: CollisionVector = ron::de::from_str(&ron::ser::to_string(&CollisionVector::Resolve(Vector2::new(1.0f32, -2.0))).unwrap()).unwrap();
+        assert!(matches!(vector, CollisionVector::Resolve(v) if v == Vector2::new(1.0, -2.0)));
+        let third = Fixed::from_f64(1.0 / 3.0);
+        assert_eq!(ron::de::from_str::<Fixed>(&ron::ser::to_string(&third).unwrap()).unwrap(), third);
+    }
+    #[test]
+    #[cfg(feature = "tiled")]
+    fn tiled_maps() {
+        use std::io::Write;
+        use std::path::Path;
+        use base64::Engine;
+        use nalgebra::Point2;
+        use crate::shape::Aabb;
+        use crate::tiled::{collision_world, TiledCollider, TiledError, TiledLoader};
+        let tmx = r#"<map orientation="orthogonal" width="4" height="3" tilewidth="16" tileheight="16">
+            <tileset firstgid="1" name="ground" tilewidth="16" tileheight="16">
+                <tile id="0" type="solid"><objectgroup><object x="0" y="0" width="16" height="16"/></objectgroup></tile>
+                <tile id="1" type="slope"><objectgroup><object x="0" y="0"><polygon points="0,16 16,0 16,16"/></object></objectgroup></tile>
+            </tileset>
+            <layer name="ground" width="4" height="3"><data encoding="csv">1,1,1,0,1,1,1,0,0,0,2,0</data></layer>
+            <objectgroup name="walls">
+                <object name="ell" x="100" y="0"><polygon points="0,0 20,0 20,10 10,10 10,20 0,20"/></object>
+                <object name="pond" type="water" x="200" y="0" width="20" height="10"><ellipse/></object>
+                <object name="spawn" x="5" y="5"><point/></object>
+            </objectgroup>
+        </map>"#;
+        let loader = TiledLoader::new();
+        let colliders: Vec<TiledCollider> = loader.load_tmx(tmx, Path::new("")).unwrap();
+        let bounds = |class: &str| colliders.iter().filter(|collider| collider.class == class).map(|collider| collider.shape.aabb()).collect::<Vec<_>>();
+        assert_eq!(bounds("solid"), vec![Aabb::new(Point2::new(0.0, 0.0), Point2::new(48.0, 32.0))]);
+        assert_eq!(bounds("slope"), vec![Aabb::new(Point2::new(32.0, 32.0), Point2::new(48.0, 48.0))]);
+        assert_eq!(colliders.iter().filter(|collider| collider.name == "ell").count(), 2);
+        let pond = &bounds("water")[0];
+        assert!((pond.min - Point2::new(200.0, 0.0)).norm() < 1e-4 && (pond.max - Point2::new(220.0, 10.0)).norm() < 1e-4);
+        assert!(colliders.iter().all(|collider| collider.name != "spawn"));
+        let world = collision_world(&colliders);
+        assert_eq!(world.query_point(Point2::new(40.0, 20.0), !0).len(), 1);
+
+        let mut zlib = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        for gid in [1u32, 1, 0x8000_0002, 0] {
+            zlib.write_all(&gid.to_le_bytes()).unwrap();
+        }
+        let data = base64::engine::general_purpose::STANDARD.encode(zlib.finish().unwrap());
+        let json = format!(r#"{{"orientation": "orthogonal", "tilewidth": 16, "tileheight": 16,
+            "tilesets": [{{"firstgid": 1, "tilewidth": 16, "tileheight": 16, "tiles": [
+                {{"id": 0, "type": "solid", "objectgroup": {{"objects": [{{"x": 0, "y": 0, "width": 16, "height": 16}}]}}}},
+                {{"id": 1, "type": "slope", "objectgroup": {{"objects": [{{"x": 0, "y": 0, "polygon": [{{"x": 0, "y": 16}}, {{"x": 16, "y": 0}}, {{"x": 16, "y": 16}}]}}]}}}}
+            ]}}],
+            "layers": [{{"type": "group", "name": "level", "offsetx": 0, "offsety": 100, "layers": [
+                {{"type": "tilelayer", "name": "ground", "width": 4, "height": 1, "encoding": "base64", "compression": "zlib", "data": "{}"}}
+            ]}}]}}"#, data);
+        let colliders: Vec<TiledCollider> = TiledLoader { flip_y: true, ..TiledLoader::new() }.load_json(&json, Path::new("")).unwrap();
+        assert_eq!(colliders.len(), 2);
+        let shape = |class: &str| &colliders.iter().find(|collider| collider.class == class).unwrap().shape;
+        assert_eq!(shape("solid").aabb(), Aabb::new(Point2::new(0.0, -116.0), Point2::new(32.0, -100.0)));
+        assert_eq!(shape("slope").aabb(), Aabb::new(Point2::new(32.0, -116.0), Point2::new(48.0, -100.0)));
+        assert!(shape("slope").contains(Point2::new(34.0, -114.0)));
+        assert!(!shape("slope").contains(Point2::new(46.0, -102.0)));
+        let widthless = r#"{"orientation": "orthogonal", "tilewidth": 16, "tileheight": 16, "tilesets": [],
+            "layers": [{"type": "tilelayer", "name": "ground", "data": [1, 1]}]}"#;
+        assert!(matches!(TiledLoader::new().load_json::<f32>(widthless, Path::new("")), Err(TiledError::Invalid(_))));
+        let sizeless = json.replacen(r#""firstgid": 1, "tilewidth": 16, "tileheight": 16, "#, r#""firstgid": 1, "#, 1);
+        assert!(matches!(TiledLoader::new().load_json::<f32>(&sizeless, Path::new("")), Err(TiledError::Invalid(_))));
+        let truncated = base64::engine::general_purpose::STANDARD.encode([1u8, 0, 0, 0, 1, 0]);
+        let layer = format!(r#"{{"orientation": "orthogonal", "tilewidth": 16, "tileheight": 16, "tilesets": [],
+            "layers": [{{"type": "tilelayer", "name": "ground", "width": 2, "encoding": "base64", "data": "{}"}}]}}"#, truncated);
+        assert!(matches!(TiledLoader::new().load_json::<f32>(&layer, Path::new("")), Err(TiledError::Invalid(_))));
+    }
+    #[test]
+    fn svg_export() {
+        use nalgebra::{Point2, Vector2};
+        use crate::shape::Shape;
+        use crate::svg::DebugDrawing;
+        let a = Shape::rect(10.0, 10.0);
+        let b = Shape::rect_centered(10.0, 10.0);
+        let mut drawing = DebugDrawing::new();
+        drawing.shape(&a, "steelblue").shape(&b, "orange");
+        let vector = a.collide(&b).unwrap();
+        drawing.collision(&a, &vector, "red").resolution(&a, Vector2::new(5.0, 0.0), "red").contacts(&a, &b, "black");
+        drawing.ray(Point2::new(-20.0, 2.0), Point2::new(-5.0, 2.0), "green");
+        let svg = drawing.to_string();
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-21.5 -11.5 33 18" width="800""#));
+        assert!(svg.contains(r#"<polygon points="0,0 10,0 10,-10 0,-10" fill="steelblue""#));
+        assert!(svg.contains(r#"<circle cx="0" cy="0" r="0.375" fill="black"/>"#));
+        assert!(svg.contains(r#"<circle cx="5" cy="0" r="0.375" fill="black"/>"#));
+        assert!(svg.contains(r#"<circle cx="0" cy="-5" r="0.375" fill="black"/>"#));
+        assert!(svg.contains(r#"<polyline points="-20,-2 -5,-2" fill="none" stroke="green""#));
+        assert_eq!(svg.matches("<polyline").count(), 3);
+        assert!(svg.ends_with("</svg>"));
+    }
+    #[cfg(feature = "svg_import")]
+    #[test]
+    fn svg_import() {
+        use nalgebra::Point2;
+        use crate::svg::{by_name, SvgError, SvgLoader};
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="200">
+            <defs><rect id="hidden" width="5" height="5"/></defs>
+            <g id="layer1" transform="translate(100,0)">
+                <path id="ell" d="m 0,0 h 30 v 10 h -20 v 20 h -10 z"/>
+            </g>
+            <path id="dome" d="M 60 0 A 10 10 0 0 1 80 0 Z"/>
+            <path d="M0 200 C 0 190 20 190 20 200 Q 10 210 0,200z"/>
+            <rect id="box" x="0" y="50" width="10px" height="10" rx="2"/>
+            <circle id="coin" cx="50" cy="50" r="5"/>
+            <polygon id="tri" points="0,100 10,100 5,90" transform="rotate(90 5 95)"/>
+        </svg>"#;
+        let shapes = SvgLoader::new().load_str::<f64>(svg).unwrap();
+        let named = by_name(shapes.clone());
+        assert!(!named.contains_key("hidden"));
+        let inside = |name: &str, point: Point2<f64>| named[name].iter().any(|shape| shape.contains(point));
+        assert!(named["ell"].len() >= 2);
+        assert!(inside("ell", Point2::new(125.0, 5.0)) && inside("ell", Point2::new(105.0, 25.0)));
+        assert!(!inside("ell", Point2::new(120.0, 20.0)));
+        assert!(inside("dome", Point2::new(70.0, -9.0)) && !inside("dome", Point2::new(70.0, 1.0)));
+        assert!(inside("", Point2::new(10.0, 195.0)) && inside("", Point2::new(10.0, 204.0)));
+        assert!(inside("box", Point2::new(5.0, 55.0)) && !inside("box", Point2::new(0.1, 50.1)));
+        // Every vertex lies on the circle, and the flattened edges stay within the tolerance of it.
+        for point in named["coin"].iter().flat_map(|shape| shape.iter_points()) {
+            assert!(((point - Point2::new(50.0, 50.0)).norm() - 5.0).abs() < 1e-9);
+        }
+        assert!(inside("coin", Point2::new(54.7, 50.0)) && inside("coin", Point2::new(50.0, 45.3)));
+        assert!(inside("tri", Point2::new(1.0, 95.0)) && !inside("tri", Point2::new(5.0, 91.0)));
+        let flipped = SvgLoader { flip_y: true, ..SvgLoader::new() }.load_str::<f32>(svg).unwrap();
+        assert_eq!(flipped.len(), shapes.len());
+        assert!(flipped.iter().any(|piece| piece.name == "coin" && piece.shape.contains(Point2::new(50.0, -50.0))));
+        let broken = r#"<svg><path id="bad" d="M 0 0 L 10 x"/></svg>"#;
+        assert!(matches!(SvgLoader::new().load_str::<f32>(broken), Err(SvgError::Invalid(_))));
+        let exact = SvgLoader { tolerance: 0.0, ..SvgLoader::new() };
+        assert!(matches!(exact.load_str::<f32>(svg), Err(SvgError::Invalid(_))));
+    }
+    #[test]
     fn eq_test() {
-        use amethyst::core::math::Vector2;
-        assert_eq!(Vector2::new(1.0, 0.0) > Vector2::new(0.0, 1.1), false);
+        use nalgebra::Vector2;
+        assert_ne!(Vector2::new(1.0, 0.0).partial_cmp(&Vector2::new(0.0, 1.1)), Some(std::cmp::Ordering::Greater));
     }
     #[test]
     fn raycast_test() {
-        use amethyst::core::math::Point2;
+        use nalgebra::Point2;
         use crate::shape::Shape;
         use crate::processing::Raycast;
         let shape_a = Shape::new(
@@ -28,15 +400,454 @@ mod tests {
         assert_ne!(Raycast::new(&[(shape_a, ())], Point2::new(-1.0, -1.0), 1.0).next(), None);
     }
     #[test]
+    fn steep_lines() {
+        use nalgebra::Point2;
+        use crate::shape::Shape;
+        use crate::processing::Raycast;
+        let sliver = Shape::new([Point2::new(0.0, 0.0), Point2::new(1.0e-4, 100.0), Point2::new(10.0, 100.0), Point2::new(10.0, 0.0)]);
+        assert!(sliver.contains(Point2::new(0.01, 50.0)));
+        assert!(!sliver.contains(Point2::new(-0.01, 50.0)));
+        let shapes = [(sliver, ())];
+        let hit = Raycast::new(&shapes, Point2::new(-5.0, 50.0), 0.0).next().unwrap();
+        assert!(hit.dist.x.abs() < 1e-3 && (hit.dist.y - 50.0).abs() < 1e-3);
+        let up = Raycast::new(&shapes, Point2::new(5.0, -50.0), std::f32::consts::FRAC_PI_2).next().unwrap();
+        assert!((up.dist.x - 5.0).abs() < 1e-3 && up.dist.y.abs() < 1e-3);
+    }
+    #[test]
+    fn fixed_point() {
+        use nalgebra::Point2;
+        use crate::shape::GenericShape;
+        use crate::processing::Raycast;
+        use crate::scalar::{Fixed, Real};
+        let corners = |x: f64| [(x, 0.0), (x, 10.0), (x + 10.0, 10.0), (x + 10.0, 0.0)];
+        let float = |x: f64| GenericShape::new(corners(x).iter().map(|&(x, y)| Point2::new(x, y)));
+        let fixed = |x: f64| GenericShape::new(corners(x).iter().map(|&(x, y)| Point2::new(Fixed::from_f64(x), Fixed::from_f64(y))));
+        let expected = float(0.0).collide(&float(7.5)).unwrap();
+        let vector = fixed(0.0).collide(&fixed(7.5)).unwrap();
+        assert!((vector.x.to_f64() - expected.x).abs() < 1e-6 && (vector.y.to_f64() - expected.y).abs() < 1e-6);
+        let (sin, cos) = Fixed::from_f64(2.5).sin_cos();
+        assert!((sin.to_f64() - 2.5f64.sin()).abs() < 1e-8 && (cos.to_f64() - 2.5f64.cos()).abs() < 1e-8);
+        assert!((Fixed::from_f64(-1.0).atan2(Fixed::from_f64(-2.0)).to_f64() - (-1.0f64).atan2(-2.0)).abs() < 1e-8);
+        let expected = Raycast::new(&[(float(0.0), ())], Point2::new(-1.0, -1.0), 1.0).next().unwrap().dist;
+        let start = Point2::new(Fixed::from_f64(-1.0), Fixed::from_f64(-1.0));
+        let hit = Raycast::new(&[(fixed(0.0), ())], start, Fixed::from_int(1)).next().unwrap().dist;
+        assert!((hit.x.to_f64() - expected.x).abs() < 1e-6 && (hit.y.to_f64() - expected.y).abs() < 1e-6);
+        // Far beyond the 46341 whose square overflows, lengths, cross products and collisions still match.
+        let expected = float(1.0e6).collide(&float(1.0e6 + 7.5)).unwrap();
+        let vector = fixed(1.0e6).collide(&fixed(1.0e6 + 7.5)).unwrap();
+        assert!((vector.x.to_f64() - expected.x).abs() < 1e-6 && (vector.y.to_f64() - expected.y).abs() < 1e-6);
+        let far = nalgebra::Vector2::new(Fixed::from_f64(3.0e6), Fixed::from_f64(4.0e6));
+        assert_eq!(crate::scalar::magnitude(&far), Fixed::from_f64(5.0e6));
+        let (a, b) = (Fixed::from_f64(1.0e5), Fixed::from_f64(1.0e5 + 1.0));
+        assert_eq!(Fixed::cross(a, b, a, a), Fixed::from_f64(-1.0e5));
+    }
+    #[test]
+    #[cfg(feature = "amethyst")]
     fn transformation() {
         use amethyst::core::math::{Point2, Translation3, UnitQuaternion, Vector3};
         use amethyst::core::transform::Transform;
         use crate::shape::Shape;
-        let mut a = Shape::new(&[Point2::new(0.0, 0.0), Point2::new(0.0, 10.0), Point2::new(10.0, 10.0), Point2::new(10.0, 0.0)]);
+        let mut a = Shape::new([Point2::new(0.0, 0.0), Point2::new(0.0, 10.0), Point2::new(10.0, 10.0), Point2::new(10.0, 0.0)]);
         a.set_transformation(&Transform::default());
         let mut b = a.clone();
         let transform = Transform::new(Translation3::new(7.4, 7.5, 0.), UnitQuaternion::identity(), Vector3::new(1., 1., 1.));
         b.set_transformation(&transform);
         assert!(b.collide(&a).is_some());
     }
+    #[test]
+    fn world_queries() {
+        use nalgebra::Point2;
+        use crate::shape::Shape;
+        use crate::processing::world::{CollisionWorld, ALL_LAYERS};
+        let square = |x: f32, y: f32| Shape::new([Point2::new(x, y), Point2::new(x, y + 10.0), Point2::new(x + 10.0, y + 10.0), Point2::new(x + 10.0, y)]);
+        let mut world = CollisionWorld::with_cell_size(16.0);
+        world.insert(1, square(0.0, 0.0), 1);
+        world.insert(2, square(100.0, 0.0), 2);
+        assert_eq!(world.query_point(Point2::new(5.0, 5.0), ALL_LAYERS), vec![1]);
+        assert!(world.query_point(Point2::new(5.0, 5.0), 2).is_empty());
+        let hits = world.raycast(Point2::new(-20.0, 5.0), 0.0, ALL_LAYERS);
+        assert_eq!(hits.iter().map(|hit| hit.key).collect::<Vec<_>>(), vec![1, 2]);
+        assert!((hits[0].distance - 20.0).abs() < 1e-4);
+        let hit = world.shape_cast(&square(20.0, 0.0), 0.0, 200.0, 2).unwrap();
+        assert_eq!(hit.key, 2);
+        assert!((hit.distance - 70.0).abs() < 1e-4);
+        // A ray crossing more cells than fit in an i32 still finds the shapes at both ends.
+        let far = |x: f64| crate::shape::GenericShape::new([Point2::new(x, 0.0), Point2::new(x + 10.0, 0.0), Point2::new(x + 10.0, 10.0), Point2::new(x, 10.0)]);
+        let mut world = CollisionWorld::with_cell_size(1.0);
+        world.insert(1, far(-1.5e9), ALL_LAYERS);
+        world.insert(2, far(1.5e9), ALL_LAYERS);
+        let hits = world.raycast(Point2::new(-2.0e9, 5.0), 0.0, ALL_LAYERS);
+        assert_eq!(hits.iter().map(|hit| hit.key).collect::<Vec<_>>(), vec![1, 2]);
+        for size in [0.0, -16.0, f32::NAN] {
+            assert!(std::panic::catch_unwind(|| CollisionWorld::<u32>::with_cell_size(size)).is_err());
+        }
+    }
+    #[test]
+    #[cfg(feature = "amethyst")]
+    fn sync_changed_only() {
+        use amethyst::core::ecs::prelude::*;
+        use amethyst::core::math::{Point2, Vector2};
+        use amethyst::core::transform::Transform;
+        use crate::shape::Shape;
+        use crate::processing::system::{ShapeSync, SyncSpace};
+        let mut world = World::new();
+        let mut sync = ShapeSync::with_space(SyncSpace::Local);
+        System::setup(&mut sync, &mut world);
+        let mut transform = Transform::default();
+        transform.set_translation_xyz(5., 0., 0.);
+        let entity = world.create_entity()
+            .with(transform)
+            .with(Shape::new([Point2::new(0.0, 0.0), Point2::new(0.0, 10.0), Point2::new(10.0, 10.0), Point2::new(10.0, 0.0)]))
+            .build();
+        sync.run_now(&world);
+        let first = |world: &World| *world.read_storage::<Shape>().get(entity).unwrap().iter_points().next().unwrap();
+        assert_eq!(first(&world), Point2::new(5.0, 0.0));
+        world.write_storage::<Shape>().get_mut(entity).unwrap().transform(|point| point + Vector2::new(1.0, 0.0));
+        sync.run_now(&world);
+        assert_eq!(first(&world), Point2::new(1.0, 0.0));
+        world.write_storage::<Transform>().get_mut(entity).unwrap().set_translation_xyz(2., 0., 0.);
+        sync.run_now(&world);
+        assert_eq!(first(&world), Point2::new(2.0, 0.0));
+    }
+    #[test]
+    #[cfg(feature = "amethyst")]
+    fn shape_assets() {
+        use amethyst::assets::{AssetStorage, ProcessableAsset, ProcessingState};
+        use amethyst::core::ecs::prelude::*;
+        use amethyst::core::math::Point2;
+        use amethyst::core::transform::Transform;
+        use crate::shape::{Shape, ShapeAsset};
+        use crate::processing::system::{ShapeSync, SyncSpace};
+        let load = |ron: &str| match ShapeAsset::process(ron::de::from_str(ron).unwrap()).unwrap() {
+            ProcessingState::Loaded(asset) => asset,
+            ProcessingState::Loading(_) => unreachable!(),
+        };
+        let mut world = World::new();
+        let mut sync = ShapeSync::with_space(SyncSpace::Local);
+        System::setup(&mut sync, &mut world);
+        let handle = world.write_resource::<AssetStorage<ShapeAsset>>().insert(load("(shape: Rect(width: 10, height: 10))"));
+        let mut transform = Transform::default();
+        transform.set_translation_xyz(5., 0., 0.);
+        let entity = world.create_entity().with(transform).with(handle.clone()).build();
+        sync.run_now(&world);
+        let aabb = |world: &World| world.read_storage::<Shape>().get(entity).unwrap().aabb();
+        assert_eq!(aabb(&world).max, Point2::new(15.0, 10.0));
+        world.write_resource::<AssetStorage<ShapeAsset>>().replace(&handle, load("(shape: Rect(width: 20, height: 10))"));
+        sync.run_now(&world);
+        assert_eq!(aabb(&world).max, Point2::new(25.0, 10.0));
+    }
+    #[test]
+    #[cfg(feature = "amethyst")]
+    fn sync_global() {
+        use amethyst::core::ecs::prelude::*;
+        use nalgebra::Point2;
+        use amethyst::core::transform::{Parent, Transform, TransformBundle};
+        use amethyst::core::SystemBundle;
+        use crate::shape::Shape;
+        use crate::processing::system::{ShapeSync, SyncSpace};
+        assert_eq!(SyncSpace::default(), SyncSpace::Local);
+        let mut world = World::new();
+        let mut builder = DispatcherBuilder::new();
+        TransformBundle::new().build(&mut world, &mut builder).unwrap();
+        builder.add(ShapeSync::with_space(SyncSpace::Global), "shape_sync", &["transform_system"]);
+        let mut dispatcher = builder.build();
+        dispatcher.setup(&mut world);
+        let mut transform = Transform::default();
+        transform.set_translation_xyz(100., 0., 0.);
+        let parent = world.create_entity().with(transform).build();
+        let child = world.create_entity()
+            .with(Parent::new(parent))
+            .with(Transform::default())
+            .with(Shape::new([Point2::new(0.0, 0.0), Point2::new(0.0, 10.0), Point2::new(10.0, 10.0), Point2::new(10.0, 0.0)]))
+            .build();
+        dispatcher.dispatch(&world);
+        world.maintain();
+        let shapes = world.read_storage::<Shape>();
+        assert_eq!(*shapes.get(child).unwrap().iter_points().next().unwrap(), Point2::new(100.0, 0.0));
+    }
+    /// Tag of the colliders in the system tests.
+    #[cfg(feature = "specs")]
+    #[derive(Clone, Debug, PartialEq)]
+    struct Tag(u32);
+    #[cfg(feature = "specs")]
+    impl specs::Component for Tag {
+        type Storage = specs::VecStorage<Self>;
+    }
+    /// A world running `systems`, then `WorldSync` when `world_sync` is set, then `collider`.
+    #[cfg(feature = "specs")]
+    fn collider_setup(
+        systems: specs::DispatcherBuilder<'static, 'static>,
+        world_sync: bool,
+        collider: crate::processing::system::ColliderSys<Tag>,
+    ) -> (specs::World, specs::Dispatcher<'static, 'static>) {
+        use specs::prelude::*;
+        let mut builder = systems.with_barrier();
+        if world_sync {
+            builder.add(crate::processing::system::WorldSync::new(), "world_sync", &[]);
+            builder.add_barrier();
+        }
+        let mut dispatcher = builder.with(collider, "collider_sys", &[]).build();
+        let mut world = World::new();
+        dispatcher.setup(&mut world);
+        (world, dispatcher)
+    }
+    #[cfg(feature = "specs")]
+    fn square(x: f32, y: f32, size: f32) -> crate::shape::Shape {
+        use nalgebra::Point2;
+        crate::shape::Shape::new([Point2::new(x, y), Point2::new(x + size, y), Point2::new(x + size, y + size), Point2::new(x, y + size)])
+    }
+    /// Tags of everything `entity` collided with, in order.
+    #[cfg(feature = "specs")]
+    fn collided_tags(world: &specs::World, entity: specs::Entity) -> Vec<Tag> {
+        use specs::prelude::*;
+        let collisions = world.read_storage::<crate::processing::system::Collisions<Tag>>();
+        collisions.get(entity).unwrap().collisions().iter().map(|(_, tag)| tag.clone()).collect()
+    }
+    #[test]
+    #[cfg(feature = "amethyst")]
+    fn parallel_collisions() {
+        use amethyst::core::ecs::prelude::*;
+        use amethyst::core::transform::Transform;
+        use crate::processing::system::{ColliderSys, Collisions, ShapeSync, SyncSpace};
+        let systems = DispatcherBuilder::new().with(ShapeSync::with_space(SyncSpace::Local), "shape_sync", &[]);
+        let (mut world, mut dispatcher) = collider_setup(systems, true, ColliderSys::new());
+        let entities: Vec<_> = [0.0, 4.0, 8.0, 200.0].iter().enumerate().map(|(i, &x)| {
+            world.create_entity()
+                .with(Transform::default())
+                .with(square(x, 0.0, 10.0))
+                .with(Tag(i as u32))
+                .with(Collisions::<Tag>::new())
+                .build()
+        }).collect();
+        dispatcher.dispatch(&world);
+        assert_eq!(collided_tags(&world, entities[0]), vec![Tag(1), Tag(2)]);
+        assert_eq!(collided_tags(&world, entities[1]), vec![Tag(0), Tag(2)]);
+        assert!(collided_tags(&world, entities[3]).is_empty());
+    }
+    #[test]
+    #[cfg(feature = "bevy")]
+    fn bevy_plugin() {
+        use bevy::prelude::*;
+        use bevy::ecs::system::RunSystemOnce;
+        use nalgebra::Point2;
+        use crate::shape::Shape;
+        use crate::processing::bevy_plugin::{CollisionEvent, Collisions, Raycaster, ShapekitPlugin};
+        use crate::processing::world::ALL_LAYERS;
+        let mut app = App::new();
+        app.add_plugins((bevy::core::TaskPoolPlugin::default(), ShapekitPlugin));
+        let square = Shape::new([Point2::new(0.0, 0.0), Point2::new(0.0, 10.0), Point2::new(10.0, 10.0), Point2::new(10.0, 0.0)]);
+        let a = app.world_mut().spawn((GlobalTransform::default(), square.clone(), Collisions::new())).id();
+        let b = app.world_mut().spawn((GlobalTransform::from_translation(Vec3::new(5.0, 5.0, 0.0)), square.clone())).id();
+        let c = app.world_mut().spawn((GlobalTransform::from_translation(Vec3::new(100.0, 0.0, 0.0)), square)).id();
+        app.update();
+        let collisions = app.world().get::<Collisions>(a).unwrap();
+        assert_eq!(collisions.collisions().iter().map(|(_, other)| *other).collect::<Vec<_>>(), vec![b]);
+        let events = app.world().resource::<Events<CollisionEvent>>();
+        assert_eq!(events.get_reader().read(events).map(|event| event.other).collect::<Vec<_>>(), vec![b]);
+        let hits = app.world_mut().run_system_once(|raycaster: Raycaster| {
+            raycaster.raycast(Point2::new(-10.0, 2.0), 0.0, ALL_LAYERS).into_iter().map(|hit| hit.key).collect::<Vec<_>>()
+        });
+        assert_eq!(hits, vec![a, c]);
+    }
+    #[test]
+    #[cfg(feature = "bevy")]
+    fn bevy_layers() {
+        use bevy::prelude::*;
+        use crate::shape::Shape;
+        use crate::processing::bevy_plugin::{Collisions, ShapekitPlugin};
+        use crate::processing::world::Layers;
+        let mut app = App::new();
+        app.add_plugins((bevy::core::TaskPoolPlugin::default(), ShapekitPlugin));
+        let a = app.world_mut().spawn((GlobalTransform::default(), Shape::rect(10.0, 10.0), Layers(1), Collisions::new())).id();
+        let b = app.world_mut().spawn((GlobalTransform::default(), Shape::rect_centered(10.0, 10.0), Layers(2), Collisions::new())).id();
+        let c = app.world_mut().spawn((GlobalTransform::default(), Shape::rect_centered(10.0, 10.0), Layers(3))).id();
+        app.update();
+        let others = |entity| app.world().get::<Collisions>(entity).unwrap().collisions().iter().map(|(_, other)| *other).collect::<Vec<_>>();
+        assert_eq!(others(a), vec![c]);
+        assert_eq!(others(b), vec![c]);
+    }
+    #[test]
+    #[cfg(feature = "specs")]
+    fn sensors() {
+        use specs::prelude::*;
+        use crate::shape::Shape;
+        use crate::processing::system::{ColliderSys, Collisions};
+        use crate::processing::world::Sensor;
+        let (mut world, mut dispatcher) = collider_setup(DispatcherBuilder::new(), true, ColliderSys::new());
+        let player = world.create_entity()
+            .with(Shape::rect(10.0, 10.0))
+            .with(Tag(0))
+            .with(Collisions::<Tag>::new())
+            .build();
+        world.create_entity()
+            .with(Shape::rect_centered(10.0, 10.0))
+            .with(Tag(1))
+            .with(Sensor)
+            .build();
+        dispatcher.dispatch(&world);
+        let collisions = world.read_storage::<Collisions<Tag>>();
+        let player = collisions.get(player).unwrap();
+        assert_eq!(player.collisions().len(), 1);
+        assert_eq!(player.resolution(), nalgebra::Vector2::new(0.0, 0.0));
+    }
+    #[test]
+    #[cfg(feature = "specs")]
+    fn collider_layers() {
+        use specs::prelude::*;
+        use crate::shape::Shape;
+        use crate::processing::system::{ColliderSys, Collisions};
+        use crate::processing::world::Layers;
+        for layered in [true, false] {
+            let collider = if layered { ColliderSys::with_layers() } else { ColliderSys::new() };
+            let (mut world, mut dispatcher) = collider_setup(DispatcherBuilder::new(), true, collider);
+            let player = world.create_entity()
+                .with(Shape::rect(10.0, 10.0))
+                .with(Tag(0))
+                .with(Layers(1))
+                .with(Collisions::<Tag>::new())
+                .build();
+            for (tag, layers) in [(1, 2), (2, 3)] {
+                world.create_entity()
+                    .with(Shape::rect_centered(10.0, 10.0))
+                    .with(Tag(tag))
+                    .with(Layers(layers))
+                    .build();
+            }
+            dispatcher.dispatch(&world);
+            let expected = if layered { vec![Tag(2)] } else { vec![Tag(1), Tag(2)] };
+            assert_eq!(collided_tags(&world, player), expected);
+        }
+    }
+    #[test]
+    #[cfg(feature = "specs")]
+    fn collider_matches_serial() {
+        use specs::prelude::*;
+        use crate::shape::Shape;
+        use crate::processing::system::{ColliderSys, Collisions};
+        let shapes: Vec<Shape> = (0..40).map(|i| square((i * 7 % 23) as f32 * 3.0, (i * 11 % 17) as f32 * 4.0, 10.0)).collect();
+        // Every entity against every other one, in entity order, as ColliderSys did before the broad phase.
+        let serial: Vec<Vec<_>> = shapes.iter().enumerate().map(|(a, shape_a)| {
+            shapes.iter().enumerate()
+                .filter(|(b, _)| *b != a)
+                .filter_map(|(b, shape_b)| Some((shape_a.collide(shape_b)?, Tag(b as u32))))
+                .collect()
+        }).collect();
+        assert!(serial.iter().any(|contacts| contacts.len() > 1));
+        for world_sync in [true, false] {
+            let (mut world, mut dispatcher) = collider_setup(DispatcherBuilder::new(), world_sync, ColliderSys::new());
+            let entities: Vec<_> = shapes.iter().enumerate().map(|(i, shape)| {
+                world.create_entity().with(shape.clone()).with(Tag(i as u32)).with(Collisions::<Tag>::new()).build()
+            }).collect();
+            dispatcher.dispatch(&world);
+            let collisions = world.read_storage::<Collisions<Tag>>();
+            for (entity, expected) in entities.iter().zip(&serial) {
+                assert_eq!(collisions.get(*entity).unwrap().collisions(), &expected[..]);
+            }
+        }
+    }
+    #[test]
+    #[cfg(feature = "specs")]
+    fn collider_without_world_sync() {
+        use specs::prelude::*;
+        use crate::processing::system::{ColliderSys, Collisions};
+        use crate::processing::world::CollisionWorld;
+        let (mut world, mut dispatcher) = collider_setup(DispatcherBuilder::new(), false, ColliderSys::new());
+        let entities: Vec<_> = [0.0, 5.0, 100.0].iter().enumerate().map(|(i, &x)| {
+            world.create_entity().with(square(x, 0.0, 10.0)).with(Tag(i as u32)).with(Collisions::<Tag>::new()).build()
+        }).collect();
+        dispatcher.dispatch(&world);
+        assert!(world.read_resource::<CollisionWorld<Entity>>().is_empty());
+        assert_eq!(collided_tags(&world, entities[0]), vec![Tag(1)]);
+        assert_eq!(collided_tags(&world, entities[1]), vec![Tag(0)]);
+        assert!(collided_tags(&world, entities[2]).is_empty());
+    }
+    #[test]
+    #[cfg(feature = "debug_lines")]
+    fn debug_lines() {
+        use amethyst::renderer::debug_drawing::DebugLines;
+        use specs::prelude::*;
+        use nalgebra::{Point2, Point3};
+        use crate::shape::Shape;
+        use crate::processing::debug::{ColliderDebug, DebugLinesSys};
+        use crate::processing::world::{CollisionWorld, ALL_LAYERS};
+        let mut world = World::new();
+        let mut dispatcher = DispatcherBuilder::new().with(DebugLinesSys::<()>::new(), "debug_lines", &[]).build();
+        dispatcher.setup(&mut world);
+        let entity = world.create_entity().with(Shape::rect(10.0, 10.0)).build();
+        world.write_resource::<CollisionWorld<Entity>>().insert(entity, Shape::rect(10.0, 10.0), ALL_LAYERS);
+        let mut frame = |world: &mut World| {
+            world.insert(DebugLines::new());
+            dispatcher.dispatch(world);
+            world.read_resource::<ColliderDebug>().lines().iter().map(|(start, end, _)| (*start, *end)).collect::<Vec<_>>()
+        };
+        let cast = |world: &World| world.read_resource::<CollisionWorld<Entity>>().raycast(Point2::new(-10.0, 5.0), 0.0, ALL_LAYERS);
+        world.write_resource::<ColliderDebug>().record_ray(Point2::new(0.0, 0.0), Point2::new(20.0, 0.0));
+        cast(&world);
+        assert!(frame(&mut world).is_empty());
+        {
+            let mut debug = world.write_resource::<ColliderDebug>();
+            debug.enabled = true;
+            debug.ray_frames = 2;
+        }
+        let p = |x: f32, y: f32| Point3::new(x, y, 0.0);
+        // The four edges and the two strokes of the center cross.
+        let shape = vec![
+            (p(0.0, 0.0), p(10.0, 0.0)),
+            (p(10.0, 0.0), p(10.0, 10.0)),
+            (p(10.0, 10.0), p(0.0, 10.0)),
+            (p(0.0, 10.0), p(0.0, 0.0)),
+            (p(3.0, 5.0), p(7.0, 5.0)),
+            (p(5.0, 3.0), p(5.0, 7.0)),
+        ];
+        assert_eq!(frame(&mut world), shape);
+        // Rays cast through the world are drawn up to their first hit for `ray_frames` frames.
+        cast(&world);
+        let mut with_ray = shape.clone();
+        with_ray.push((p(-10.0, 5.0), p(0.0, 5.0)));
+        assert_eq!(frame(&mut world), with_ray);
+        assert_eq!(frame(&mut world), with_ray);
+        assert_eq!(frame(&mut world), shape);
+        assert!(world.read_resource::<ColliderDebug>().lines().iter().all(|(_, _, color)| *color == ColliderDebug::new().default_color));
+    }
+    #[test]
+    #[cfg(feature = "specs")]
+    fn pose_sync() {
+        use specs::prelude::*;
+        use nalgebra::Vector2;
+        use crate::pose::Pose;
+        use crate::processing::system::{ColliderSys, Collisions, PoseSync};
+        let systems = DispatcherBuilder::new().with(PoseSync::new(), "pose_sync", &[]);
+        let (mut world, mut dispatcher) = collider_setup(systems, true, ColliderSys::new());
+        let a = world.create_entity()
+            .with(Pose::default())
+            .with(square(0.0, 0.0, 10.0))
+            .with(Tag(0))
+            .with(Collisions::<Tag>::new())
+            .build();
+        let b = world.create_entity()
+            .with(Pose::new(Vector2::new(100.0, 0.0), 0.0, Vector2::new(1.0, 1.0)))
+            .with(square(0.0, 0.0, 10.0))
+            .with(Tag(1))
+            .build();
+        dispatcher.dispatch(&world);
+        assert!(collided_tags(&world, a).is_empty());
+        world.write_storage::<Pose>().get_mut(b).unwrap().position = Vector2::new(5.0, 5.0);
+        dispatcher.dispatch(&world);
+        assert_eq!(collided_tags(&world, a), vec![Tag(1)]);
+    }
+    #[test]
+    fn pose_algebra() {
+        use nalgebra::{Point2, Vector2};
+        use crate::pose::Pose;
+        let close = |a: Point2<f32>, b: Point2<f32>| (a - b).magnitude() < 1e-4;
+        let a = Pose::new(Vector2::new(3.0, -2.0), 0.7, Vector2::new(2.0, 2.0));
+        let b = Pose::new(Vector2::new(-1.0, 5.0), -1.9, Vector2::new(0.5, 3.0));
+        let point = Point2::new(1.5, 4.0);
+        assert!(close((a * b).transform_point(&point), a.transform_point(&b.transform_point(&point))));
+        assert!(close(a.inverse().transform_point(&a.transform_point(&point)), point));
+        assert!(close(b.inverse_transform_point(&b.transform_point(&point)), point));
+        let halfway = Pose::new(Vector2::new(0.0, 0.0), 3.0, Vector2::new(1.0, 1.0)).lerp(&Pose::new(Vector2::new(2.0, 0.0), -3.0, Vector2::new(1.0, 1.0)), 0.5);
+        assert!((halfway.rotation.cos() + 1.0).abs() < 1e-4);
+        assert!(close(Point2::from(halfway.position), Point2::new(1.0, 0.0)));
+    }
 }
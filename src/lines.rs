@@ -1,163 +1,112 @@
-use amethyst::core::math::{Point2, Vector2};
+use nalgebra::{Point2, Vector2};
 use std::ops::Deref;
+use crate::scalar::{cross, magnitude, Real};
 
-#[derive(Clone, Copy)]
-pub struct Line {
-    slope: Option<f32>,
-    constant: f32,
+/// Infinite line through `point` along `direction`.
+///
+/// Sides are decided with the sign of a cross product, so no angle needs a special case.
+#[derive(Clone, Copy, Debug)]
+pub struct GenericLine<T: Real> {
+    point: Point2<T>,
+    direction: Vector2<T>,
 }
-impl Line {
-    pub fn through(a: Point2<f32>, b: Point2<f32>) -> Line {
-        if a.x == b.x {
-            Line {
-                slope: None,
-                constant: a.x,
-            }
-        } else {
-            let m = (a.y - b.y) / (a.x - b.x);
-            Line {
-                slope: Some(m),
-                constant: a.y - (m * a.x),
-            }
+pub type Line = GenericLine<f32>;
+
+impl<T: Real> GenericLine<T> {
+    pub fn through(a: Point2<T>, b: Point2<T>) -> GenericLine<T> {
+        GenericLine {
+            point: a,
+            direction: b - a,
         }
     }
-    pub fn y(&self, x: f32) -> Option<f32> {
-        Some((self.slope? * x) + self.constant)
+    /// Positive when `point` is left of the direction, negative when right, zero when on the line.
+    pub fn side(&self, point: Point2<T>) -> T {
+        cross(&self.direction, &(point - self.point))
     }
-    pub fn initialize(self, point: Point2<f32>) -> InEq {
-        InEq {
-            greater: match self.y(point.x) {
-                Some(val) => point.y > val,
-                None => point.x > self.constant,
-            },
+    pub fn initialize(self, point: Point2<T>) -> GenericInEq<T> {
+        GenericInEq {
+            left: self.side(point) > T::zero(),
             line: self,
         }
     }
-    pub fn normal_through(&self, point: Point2<f32>) -> Line {
-        match self.slope {
-            Some(val) => {
-                if val == 0.0 {
-                    Line {
-                        slope: None,
-                        constant: point.x,
-                    }
-                } else {
-                    let m = 1.0 / val;
-                    Line {
-                        slope: Some(m),
-                        constant: point.y - (m * point.x),
-                    }
-                }
-            }
-            None => Line {
-                slope: Some(0.0),
-                constant: point.y,
-            },
+    pub fn normal_through(&self, point: Point2<T>) -> GenericLine<T> {
+        GenericLine {
+            point,
+            direction: Vector2::new(-self.direction.y, self.direction.x),
         }
     }
-    pub fn intersection(self, b: &Line) -> Option<Point2<f32>> {
-        match self.slope {
-            Some(m) => match b.slope {
-                Some(m2) => {
-                    let x = (b.constant - self.constant) / (m - m2);
-                    Some(Point2::new(x, self.y(x)?))
-                }
-                None => Some(
-                    Point2::new(b.constant, self.y(b.constant)?)
-                ),
-            },
-            None => match b.slope {
-                Some(_m) => Some(
-                    Point2::new(self.constant, b.y(self.constant)?)
-                ),
-                None => None,
-            },
+    /// Closest point on the line.
+    pub fn project(&self, point: Point2<T>) -> Point2<T> {
+        let length = self.direction.dot(&self.direction);
+        if length == T::zero() {
+            return self.point;
         }
+        self.point + self.direction * ((point - self.point).dot(&self.direction) / length)
     }
-    pub fn intersection_segment(self, other: &Line, start: Point2<f32>, end: Point2<f32>) -> Option<Point2<f32>> {
-        let isect = self.intersection(other)?;
-        if ((start.x <= isect.x && isect.x <= end.x) || (start.x >= isect.x && isect.x >= end.x))
-            && ((start.y <= isect.y && isect.y <= end.y)
-                || (start.y >= isect.y && isect.y >= end.y))
-        {
-            return Some(isect);
+    /// `None` when the lines are parallel.
+    pub fn intersection(self, b: &GenericLine<T>) -> Option<Point2<T>> {
+        let denom = cross(&self.direction, &b.direction);
+        if denom == T::zero() {
+            return None;
         }
-        None
+        Some(self.point + self.direction * (cross(&(b.point - self.point), &b.direction) / denom))
+    }
+    /// Intersection with `other`, as long as it lies between `start` and `end`, which are on this line.
+    pub fn intersection_segment(self, other: &GenericLine<T>, start: Point2<T>, end: Point2<T>) -> Option<Point2<T>> {
+        let before = other.side(start);
+        let after = other.side(end);
+        if (before > T::zero() && after > T::zero()) || (before < T::zero() && after < T::zero()) {
+            return None;
+        }
+        self.intersection(other)
     }
 }
 
 #[derive(Clone, Copy)]
-pub struct InEq {
-    line: Line,
-    greater: bool,
+pub struct GenericInEq<T: Real> {
+    line: GenericLine<T>,
+    left: bool,
 }
-impl InEq {
-    pub fn contains(&self, point: Point2<f32>) -> bool {
-        match self.line.y(point.x) {
-            Some(val) => {
-                if self.greater {
-                    point.y > val
-                } else {
-                    point.y < val
-                }
-            }
-            None => {
-                if self.greater {
-                    point.x > self.line.constant
-                } else {
-                    point.x < self.line.constant
-                }
-            }
+pub type InEq = GenericInEq<f32>;
+
+impl<T: Real> GenericInEq<T> {
+    pub fn contains(&self, point: Point2<T>) -> bool {
+        let side = self.line.side(point);
+        if self.left {
+            side > T::zero()
+        } else {
+            side < T::zero()
         }
     }
-    fn touches(&self, point: Point2<f32>) -> TouchResult {
-        if self.contains(point) {
-            TouchResult::Contain
-        } else if match self.line.y(point.x) {
-            Some(val) => {
-                if self.greater {
-                    point.y == val
-                } else {
-                    point.y == val
-                }
-            }
-            None => {
-                if self.greater {
-                    point.x == self.line.constant
-                } else {
-                    point.x == self.line.constant
-                }
-            }
-        } {
+    fn touches(&self, point: Point2<T>, tolerance: T) -> TouchResult {
+        if self.line.side(point).abs() <= tolerance * magnitude(&self.line.direction) {
             TouchResult::Touch
+        } else if self.contains(point) {
+            TouchResult::Contain
         } else {
             TouchResult::None
         }
     }
-    pub fn distance(&self, point: Point2<f32>) -> Option<CollisionVector> {
-        match self.touches(point) {
+    pub fn distance(&self, point: Point2<T>) -> Option<GenericCollisionVector<T>> {
+        self.distance_within(point, T::zero())
+    }
+    /// Like `distance`, but points closer than `tolerance` to the line count as touching it.
+    pub fn distance_within(&self, point: Point2<T>, tolerance: T) -> Option<GenericCollisionVector<T>> {
+        match self.touches(point, tolerance) {
             TouchResult::None => None,
-            TouchResult::Touch => Some(CollisionVector::Touch(self.normal_vector())),
-            TouchResult::Contain => Some(
-                CollisionVector::Resolve(
-                    self.line
-                        .normal_through(point)
-                        .intersection(&self.line)
-                        .unwrap()
-                        - point,
-                )
-            )
+            TouchResult::Touch => Some(GenericCollisionVector::Touch(self.normal_vector())),
+            TouchResult::Contain => Some(GenericCollisionVector::Resolve(self.line.project(point) - point)),
         }
     }
-    fn normal_vector(&self) -> Vector2<f32> {
-        let naive = match self.line.slope {
-            Some(m) => {
-                let rand_length = Vector2::new(m, 1.);
-                rand_length / rand_length.magnitude()
-            },
-            None => Vector2::new(1., 0.)
-        };
-        match self.greater {
+    /// Unit normal pointing into the half plane.
+    fn normal_vector(&self) -> Vector2<T> {
+        let direction = self.line.direction;
+        let length = magnitude(&direction);
+        if length == T::zero() {
+            return Vector2::new(T::zero(), T::zero());
+        }
+        let naive = Vector2::new(-direction.y, direction.x) / length;
+        match self.left {
             true => naive,
             false => -naive,
         }
@@ -170,26 +119,47 @@ enum TouchResult {
     None
 }
 
-#[derive(Debug, Clone)]
-pub enum CollisionVector {
-    Touch(Vector2<f32>),
-    Resolve(Vector2<f32>)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GenericCollisionVector<T: Real> {
+    Touch(Vector2<T>),
+    Resolve(Vector2<T>)
 }
-impl CollisionVector {
-    pub fn flip(&self) -> CollisionVector {
+/// How to separate two `Shape`s.
+pub type CollisionVector = GenericCollisionVector<f32>;
+
+impl<T: Real> GenericCollisionVector<T> {
+    /// The same contact seen from the other shape: the vector is negated and the kind is kept.
+    pub fn flip(&self) -> GenericCollisionVector<T> {
         match self {
-            Self::Touch(val) => Self::Touch(val * -1.),
-            Self::Resolve(val) => Self::Touch(val * -1.)
+            Self::Touch(val) => Self::Touch(-val),
+            Self::Resolve(val) => Self::Resolve(-val)
         }
     }
 }
-impl Deref for CollisionVector {
-    type Target = Vector2<f32>;
+impl<T: Real> Deref for GenericCollisionVector<T> {
+    type Target = Vector2<T>;
 
-    fn deref(&self) -> &Vector2<f32> {
+    fn deref(&self) -> &Vector2<T> {
         match self {
             Self::Touch(val) => val,
             Self::Resolve(val) => val
         }
     }
-}
\ No newline at end of file
+}
+/// Distance along `direction` (in multiples of its length) at which a ray from `start` crosses the segment `a`-`b`.
+pub fn ray_segment<T: Real>(start: Point2<T>, direction: Vector2<T>, a: Point2<T>, b: Point2<T>) -> Option<T> {
+    let side = b - a;
+    let denom = cross(&direction, &side);
+    if denom == T::zero() {
+        return None;
+    }
+    let offset = a - start;
+    let t = cross(&offset, &side) / denom;
+    let s = cross(&offset, &direction) / denom;
+    if t >= T::zero() && (T::zero()..=T::one()).contains(&s) {
+        Some(t)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,110 @@
+use nalgebra::{Matrix4, Point2, Vector2};
+use std::ops::Mul;
+use crate::scalar::{magnitude, Real};
+#[cfg(feature = "specs")]
+use specs::{storage::{DenseVecStorage, FlaggedStorage}, Component};
+
+/// Position, rotation (in radians) and scale of a shape in 2D.
+/// Points are scaled, then rotated, then translated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GenericPose<T: Real> {
+    pub position: Vector2<T>,
+    pub rotation: T,
+    pub scale: Vector2<T>,
+}
+/// The pose component `PoseSync` moves shapes by.
+pub type Pose = GenericPose<f32>;
+
+impl<T: Real> GenericPose<T> {
+    pub fn new(position: Vector2<T>, rotation: T, scale: Vector2<T>) -> GenericPose<T> {
+        GenericPose {
+            position,
+            rotation,
+            scale,
+        }
+    }
+    pub fn from_position(position: Vector2<T>) -> GenericPose<T> {
+        GenericPose {
+            position,
+            ..GenericPose::default()
+        }
+    }
+    /// Extracts the XY part of a 3D affine matrix, such as amethyst's `Transform::global_matrix`.
+    /// Rotations out of the XY plane and z are dropped; a mirrored matrix gets a negative y scale.
+    pub fn from_matrix(matrix: &Matrix4<T>) -> GenericPose<T> {
+        let x_axis = Vector2::new(matrix[(0, 0)], matrix[(1, 0)]);
+        let y_axis = Vector2::new(matrix[(0, 1)], matrix[(1, 1)]);
+        let det = x_axis.x * y_axis.y - x_axis.y * y_axis.x;
+        GenericPose {
+            position: Vector2::new(matrix[(0, 3)], matrix[(1, 3)]),
+            rotation: x_axis.y.atan2(x_axis.x),
+            scale: Vector2::new(
+                magnitude(&x_axis),
+                if det < T::zero() { -magnitude(&y_axis) } else { magnitude(&y_axis) },
+            ),
+        }
+    }
+    /// Scales, then rotates, then translates `point`.
+    pub fn transform_point(&self, point: &Point2<T>) -> Point2<T> {
+        let rotated = rotate(point.coords.component_mul(&self.scale), self.rotation);
+        Point2::from(rotated + self.position)
+    }
+    pub fn inverse_transform_point(&self, point: &Point2<T>) -> Point2<T> {
+        let unrotated = rotate(point.coords - self.position, -self.rotation);
+        Point2::from(unrotated.component_div(&self.scale))
+    }
+    /// The pose undoing this one. Exact when the scale is uniform.
+    pub fn inverse(&self) -> GenericPose<T> {
+        let scale = Vector2::new(T::one() / self.scale.x, T::one() / self.scale.y);
+        GenericPose {
+            position: -rotate(self.position, -self.rotation).component_mul(&scale),
+            rotation: -self.rotation,
+            scale,
+        }
+    }
+    /// The pose applying `inner` first, then `self`. Exact when `self` has a uniform scale
+    /// or `inner` has no rotation, since a 2D pose cannot represent shear.
+    pub fn compose(&self, inner: &GenericPose<T>) -> GenericPose<T> {
+        GenericPose {
+            position: self.transform_point(&Point2::from(inner.position)).coords,
+            rotation: self.rotation + inner.rotation,
+            scale: self.scale.component_mul(&inner.scale),
+        }
+    }
+    /// Interpolates towards `other`, turning the short way round.
+    pub fn lerp(&self, other: &GenericPose<T>, t: T) -> GenericPose<T> {
+        let full = T::pi() + T::pi();
+        let turn = other.rotation - self.rotation;
+        let turn = turn - full * ((turn + T::pi()) / full).floor();
+        GenericPose {
+            position: self.position + (other.position - self.position) * t,
+            rotation: self.rotation + turn * t,
+            scale: self.scale + (other.scale - self.scale) * t,
+        }
+    }
+}
+impl<T: Real> Mul for GenericPose<T> {
+    type Output = GenericPose<T>;
+    fn mul(self, inner: GenericPose<T>) -> GenericPose<T> {
+        self.compose(&inner)
+    }
+}
+
+fn rotate<T: Real>(v: Vector2<T>, angle: T) -> Vector2<T> {
+    let (sin, cos) = angle.sin_cos();
+    Vector2::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos)
+}
+impl<T: Real> Default for GenericPose<T> {
+    fn default() -> Self {
+        GenericPose {
+            position: Vector2::zeros(),
+            rotation: T::zero(),
+            scale: Vector2::new(T::one(), T::one()),
+        }
+    }
+}
+#[cfg(feature = "specs")]
+impl<T: Real> Component for GenericPose<T> {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
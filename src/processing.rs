@@ -1,54 +1,62 @@
-use crate::shape::Shape;
+use crate::shape::GenericShape;
 use crate::lines::*;
 use std::marker::PhantomData;
-use amethyst::core::math::{Point2, Vector2};
+use nalgebra::{Point2, Vector2};
+use crate::scalar::Real;
 
+#[cfg(feature = "specs")]
 pub mod system;
+#[cfg(feature = "debug_lines")]
+pub mod debug;
+#[cfg(feature = "bevy")]
+pub mod bevy_plugin;
+pub mod world;
 
-pub trait Process<'a, T, I>: Sized {
-    fn raycast(self, start: Point2<f32>, angle: f32) -> Raycast<'a, I, T>;
+pub trait Process<'a, T, I, N: Real = f32>: Sized {
+    fn raycast(self, start: Point2<N>, angle: N) -> Raycast<'a, I, T, N>;
 }
 
-impl<'a, H: Iterator<Item=S>, S: Split<'a, T>, T: 'a, I: IntoIterator<Item=S, IntoIter=H> + Sized> Process<'a, T, H> for I {
-    fn raycast(self, start: Point2<f32>, angle: f32) -> Raycast<'a, H, T> {
-        Raycast::new(self.into_iter(), start, angle)
+impl<'a, H: Iterator<Item=S>, S: Split<'a, T, N>, T: 'a, N: Real, I: IntoIterator<Item=S, IntoIter=H> + Sized> Process<'a, T, H, N> for I {
+    fn raycast(self, start: Point2<N>, angle: N) -> Raycast<'a, H, T, N> {
+        Raycast::new(self, start, angle)
     }
 }
 
-pub trait Split<'a, T> {
-    fn split(self) -> (&'a Shape, &'a T);
+pub trait Split<'a, T, N: Real = f32> {
+    fn split(self) -> (&'a GenericShape<N>, &'a T);
 }
 
-impl<'a, T> Split<'a, T> for &'a (Shape, T) {
-    fn split(self) -> (&'a Shape, &'a T) {
+impl<'a, T, N: Real> Split<'a, T, N> for &'a (GenericShape<N>, T) {
+    fn split(self) -> (&'a GenericShape<N>, &'a T) {
         (&self.0, &self.1)
     }
 }
 
-impl<'a, T> Split<'a, T> for (&'a Shape, &'a T) {
-    fn split(self) -> (&'a Shape, &'a T) {
+impl<'a, T, N: Real> Split<'a, T, N> for (&'a GenericShape<N>, &'a T) {
+    fn split(self) -> (&'a GenericShape<N>, &'a T) {
         self
     }
 }
 
 
 #[derive(Debug, PartialEq)]
-pub struct RayCollision<T> {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RayCollision<T, N: Real = f32> {
     pub tag: T,
-    pub dist: Vector2<f32>,
+    pub dist: Vector2<N>,
 }
 
-pub struct Raycast<'a, I, T> {
-    ray: Line,
-    normal: InEq,
+pub struct Raycast<'a, I, T, N: Real = f32> {
+    ray: GenericLine<N>,
+    normal: GenericInEq<N>,
     iter: I,
     ghost: PhantomData<&'a T>,
 }
-impl<'a, T: 'a, S: Split<'a, T>, I: Iterator<Item=S>> Iterator for Raycast<'a, I, T> {
-    type Item = RayCollision<&'a T>;
+impl<'a, T: 'a, N: Real, S: Split<'a, T, N>, I: Iterator<Item=S>> Iterator for Raycast<'a, I, T, N> {
+    type Item = RayCollision<&'a T, N>;
 
-    fn next(&mut self) -> Option<RayCollision<&'a T>> {
-        while let Some(spl) = self.iter.next() {
+    fn next(&mut self) -> Option<RayCollision<&'a T, N>> {
+        for spl in &mut self.iter {
             let (shape, tag) = spl.split();
             if let Some(point) = shape.receive_ray(self.ray, self.normal) {
                 return Some(RayCollision {
@@ -60,10 +68,10 @@ impl<'a, T: 'a, S: Split<'a, T>, I: Iterator<Item=S>> Iterator for Raycast<'a, I
         None
     }
 }
-impl<'a, T: 'a, S: Split<'a, T>, I: Iterator<Item=S>> Raycast<'a, I, T>  {
-    pub fn new<D: IntoIterator<IntoIter=I, Item=S>>(into_iterator: D, start: Point2<f32>, angle: f32) -> Self {
-        let calibrator = Point2::from(comp_vec(1.0, angle) + Vector2::new(start.x, start.y));
-        let ray = Line::through(start, calibrator);
+impl<'a, T: 'a, N: Real, S: Split<'a, T, N>, I: Iterator<Item=S>> Raycast<'a, I, T, N>  {
+    pub fn new<D: IntoIterator<IntoIter=I, Item=S>>(into_iterator: D, start: Point2<N>, angle: N) -> Self {
+        let calibrator = Point2::from(comp_vec(N::one(), angle) + Vector2::new(start.x, start.y));
+        let ray = GenericLine::through(start, calibrator);
         let normal = ray.normal_through(start).initialize(calibrator);
         Raycast {
             ray,
@@ -74,6 +82,7 @@ impl<'a, T: 'a, S: Split<'a, T>, I: Iterator<Item=S>> Raycast<'a, I, T>  {
     }
 }
 
-fn comp_vec(mag: f32, dir: f32) -> Vector2<f32> {
-    Vector2::new(mag * dir.cos(), mag * dir.sin())
-}
\ No newline at end of file
+fn comp_vec<N: Real>(mag: N, dir: N) -> Vector2<N> {
+    let (sin, cos) = dir.sin_cos();
+    Vector2::new(mag * cos, mag * sin)
+}
@@ -0,0 +1,179 @@
+use ::bevy::ecs::component::StorageType;
+use ::bevy::ecs::system::SystemParam;
+use ::bevy::prelude::*;
+use ::bevy::transform::TransformSystem;
+use nalgebra::{Matrix4, Point2, Vector2};
+use smallvec::SmallVec;
+
+use crate::lines::CollisionVector;
+use crate::pose::Pose;
+use crate::shape::{Aabb, Shape};
+use super::world::{CollisionWorld, Layers, Sensor, WorldHit, ALL_LAYERS};
+
+impl Component for Shape {
+    const STORAGE_TYPE: StorageType = StorageType::Table;
+}
+impl Component for Layers {
+    const STORAGE_TYPE: StorageType = StorageType::Table;
+}
+impl Component for Sensor {
+    const STORAGE_TYPE: StorageType = StorageType::SparseSet;
+}
+impl Resource for CollisionWorld<Entity> {}
+
+/// Adds the shapekit systems to `PostUpdate`, after transform propagation.
+pub struct ShapekitPlugin;
+impl Plugin for ShapekitPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CollisionWorld<Entity>>()
+            .add_event::<CollisionEvent>()
+            .add_systems(
+                PostUpdate,
+                (sync_shapes, sync_world, detect_collisions)
+                    .chain()
+                    .in_set(ShapekitSystems)
+                    .after(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ShapekitSystems;
+
+#[derive(Component, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Collisions {
+    aggregate: Vector2<f32>,
+    collisions: SmallVec<[(CollisionVector, Entity); 1]>
+}
+impl Collisions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn resolution(&self) -> Vector2<f32> {
+        self.aggregate
+    }
+    pub fn collisions(&self) -> &[(CollisionVector, Entity)] {
+        &self.collisions
+    }
+}
+impl Default for Collisions {
+    fn default() -> Self {
+        Collisions {
+            aggregate: Vector2::new(0., 0.),
+            collisions: SmallVec::new()
+        }
+    }
+}
+
+/// Sent once per frame for every entity with `Collisions` that touches another shape.
+#[derive(Event, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollisionEvent {
+    pub entity: Entity,
+    pub other: Entity,
+    pub vector: CollisionVector,
+}
+
+/// Read access to the `CollisionWorld` for systems that only need queries.
+#[derive(SystemParam)]
+pub struct Raycaster<'w> {
+    world: Res<'w, CollisionWorld<Entity>>,
+}
+impl<'w> Raycaster<'w> {
+    pub fn raycast(&self, start: Point2<f32>, angle: f32, mask: u32) -> Vec<WorldHit<Entity>> {
+        self.world.raycast(start, angle, mask)
+    }
+    pub fn raycast_first(&self, start: Point2<f32>, angle: f32, mask: u32) -> Option<WorldHit<Entity>> {
+        self.world.raycast_first(start, angle, mask)
+    }
+    pub fn shape_cast(&self, shape: &Shape, angle: f32, max_distance: f32, mask: u32) -> Option<WorldHit<Entity>> {
+        self.world.shape_cast(shape, angle, max_distance, mask)
+    }
+    pub fn query_point(&self, point: Point2<f32>, mask: u32) -> Vec<Entity> {
+        self.world.query_point(point, mask)
+    }
+    pub fn query_aabb(&self, aabb: &Aabb, mask: u32) -> Vec<Entity> {
+        self.world.query_aabb(aabb, mask)
+    }
+    pub fn overlap(&self, shape: &Shape, mask: u32) -> Vec<(Entity, CollisionVector)> {
+        self.world.overlap(shape, mask)
+    }
+}
+
+type Moved = Or<(Changed<GlobalTransform>, Added<Shape>)>;
+type Reshaped = Or<(Changed<Shape>, Changed<Layers>)>;
+
+/// Equivalent of `ShapeSync`: moves shapes whose `GlobalTransform` changed, and new shapes.
+pub fn sync_shapes(
+    mut shapes: Query<(&GlobalTransform, &mut Shape), Moved>,
+) {
+    for (transform, mut shape) in &mut shapes {
+        let matrix = transform.compute_matrix().to_cols_array();
+        shape.set_pose(&Pose::from_matrix(&Matrix4::from_column_slice(&matrix)));
+    }
+}
+
+/// Equivalent of `WorldSync`: mirrors changed shapes into the `CollisionWorld` resource.
+pub fn sync_world(
+    mut world: ResMut<CollisionWorld<Entity>>,
+    changed: Query<(Entity, &Shape, Option<&Layers>), Reshaped>,
+    shapes: Query<&Shape>,
+    mut removed_shapes: RemovedComponents<Shape>,
+    mut removed_layers: RemovedComponents<Layers>,
+) {
+    for entity in removed_shapes.read() {
+        world.remove(entity);
+    }
+    for entity in removed_layers.read() {
+        if let Ok(shape) = shapes.get(entity) {
+            world.insert(entity, shape.clone(), ALL_LAYERS);
+        }
+    }
+    for (entity, shape, layers) in &changed {
+        world.insert(entity, shape.clone(), layers.map_or(ALL_LAYERS, |layers| layers.0));
+    }
+}
+
+/// Equivalent of `ColliderSys::with_layers`: fills every `Collisions` component in parallel, in entity order.
+/// An entity only collides with entities sharing one of its `Layers`, or with everything if it has none.
+pub fn detect_collisions(
+    world: Res<CollisionWorld<Entity>>,
+    mut collisions: Query<(Entity, &Shape, Option<&Layers>, &mut Collisions)>,
+    sensors: Query<(), With<Sensor>>,
+    mut events: EventWriter<CollisionEvent>,
+) {
+    collisions.par_iter_mut().for_each(|(entity, shape_a, layers, mut collision_out)| {
+        let mask = layers.map_or(ALL_LAYERS, |layers| layers.0);
+        let mut pairs = world.query_aabb(&shape_a.aabb().expanded(world.tolerance()), mask);
+        pairs.sort();
+        let mut record = SmallVec::new();
+        let mut aggregate = Vector2::new(0., 0.);
+        for other in pairs.into_iter().filter(|other| *other != entity) {
+            let shape_b = match world.get(other) {
+                Some(shape_b) => shape_b,
+                None => continue,
+            };
+            if let Some(vector) = shape_a.collide_with_tolerance(shape_b, world.tolerance()) {
+                let sensor = sensors.contains(entity) || sensors.contains(other);
+                if let (CollisionVector::Resolve(resolution), false) = (&vector, sensor) {
+                    aggregate += resolution;
+                }
+                record.push((vector, other));
+            }
+        }
+        *collision_out = Collisions {
+            aggregate,
+            collisions: record,
+        };
+    });
+    for (entity, _, _, collision) in &collisions {
+        for (vector, other) in collision.collisions() {
+            events.send(CollisionEvent {
+                entity,
+                other: *other,
+                vector: vector.clone(),
+            });
+        }
+    }
+}
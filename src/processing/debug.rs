@@ -0,0 +1,139 @@
+use amethyst::core::math::{Point3, Vector3};
+use amethyst::renderer::{debug_drawing::DebugLines, palette::Srgba};
+use nalgebra::Point2;
+use specs::prelude::*;
+use std::marker::PhantomData;
+
+use crate::shape::Shape;
+use super::system::Collisions;
+use super::world::{CollisionWorld, Layers};
+
+/// Settings and recorded rays for `DebugLinesSys`. Nothing is drawn until `enabled` is set.
+pub struct ColliderDebug {
+    pub enabled: bool,
+    /// Outline colors by layer mask. A shape takes the first color whose mask it shares a bit with.
+    pub layer_colors: Vec<(u32, Srgba)>,
+    /// Outline color of shapes without `Layers` or without a matching entry in `layer_colors`.
+    pub default_color: Srgba,
+    pub resolution_color: Srgba,
+    pub ray_color: Srgba,
+    /// Depth at which everything is drawn.
+    pub z: f32,
+    /// Half the width of the cross marking each centroid.
+    pub center_size: f32,
+    /// Number of frames a recorded ray stays on screen.
+    pub ray_frames: u32,
+    rays: Vec<(Point2<f32>, Point2<f32>, u32)>,
+    drawn: Vec<(Point3<f32>, Point3<f32>, Srgba)>,
+}
+impl ColliderDebug {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Draws the ray from `start` to `end` for the next `ray_frames` frames. Rays cast through the
+    /// `CollisionWorld` are recorded automatically; this is for the ones cast some other way, such as `Raycast`.
+    /// Rays recorded while drawing is disabled are dropped.
+    pub fn record_ray(&mut self, start: Point2<f32>, end: Point2<f32>) {
+        if self.enabled {
+            self.rays.push((start, end, self.ray_frames));
+        }
+    }
+    /// Every line drawn in the last frame, as start, end and color.
+    pub fn lines(&self) -> &[(Point3<f32>, Point3<f32>, Srgba)] {
+        &self.drawn
+    }
+    fn color(&self, layers: Option<&Layers>) -> Srgba {
+        let mask = layers.map_or(!0, |layers| layers.0);
+        self.layer_colors
+            .iter()
+            .find(|(layer, _)| layer & mask != 0)
+            .map_or(self.default_color, |(_, color)| *color)
+    }
+}
+impl Default for ColliderDebug {
+    fn default() -> Self {
+        ColliderDebug {
+            enabled: false,
+            layer_colors: Vec::new(),
+            default_color: Srgba::new(0.2, 0.8, 0.2, 1.0),
+            resolution_color: Srgba::new(1.0, 0.2, 0.2, 1.0),
+            ray_color: Srgba::new(1.0, 1.0, 0.2, 1.0),
+            z: 0.,
+            center_size: 2.,
+            ray_frames: 30,
+            rays: Vec::new(),
+            drawn: Vec::new(),
+        }
+    }
+}
+
+/// Draws every `Shape` outline, its centroid, the resolution of its `Collisions<T>` and the rays
+/// recorded in `ColliderDebug` or cast through the `CollisionWorld` into amethyst's `DebugLines`.
+/// Run it after `ColliderSys<T>`.
+///
+/// `T` is the tag type of the `Collisions<T>` to draw, the same as for `ColliderSys<T>`, not a scalar.
+#[derive(Default)]
+pub struct DebugLinesSys<T> {
+    dud: PhantomData<T>,
+}
+impl<T> DebugLinesSys<T> {
+    pub fn new() -> Self {
+        DebugLinesSys { dud: PhantomData }
+    }
+}
+impl<'a, T> System<'a> for DebugLinesSys<T> where
+T: 'static + Send + Sync {
+    type SystemData = (
+        Write<'a, ColliderDebug>,
+        Write<'a, DebugLines>,
+        ReadStorage<'a, Shape>,
+        ReadStorage<'a, Layers>,
+        ReadStorage<'a, Collisions<T>>,
+        Read<'a, CollisionWorld<Entity>>,
+    );
+
+    fn run(&mut self, (mut debug, mut lines, shapes, layers, collisions, world): Self::SystemData) {
+        world.set_ray_log(debug.enabled);
+        if !debug.enabled {
+            debug.rays.clear();
+            debug.drawn.clear();
+            return;
+        }
+        for (start, end) in world.take_rays() {
+            debug.record_ray(start, end);
+        }
+        let z = debug.z;
+        let point = |point: &Point2<f32>| Point3::new(point.x, point.y, z);
+        let mut drawn = Vec::new();
+        let mut draw = |start: Point3<f32>, end: Point3<f32>, color: Srgba| {
+            lines.draw_line(start, end, color);
+            drawn.push((start, end, color));
+        };
+        for (shape, layers, collisions) in (&shapes, layers.maybe(), collisions.maybe()).join() {
+            let color = debug.color(layers);
+            let outline: Vec<&Point2<f32>> = shape.iter_points().collect();
+            for (a, b) in outline.iter().zip(outline.iter().cycle().skip(1)) {
+                draw(point(a), point(b), color);
+            }
+            let center = point(&shape.centroid());
+            let size = debug.center_size;
+            draw(center - Vector3::new(size, 0., 0.), center + Vector3::new(size, 0., 0.), color);
+            draw(center - Vector3::new(0., size, 0.), center + Vector3::new(0., size, 0.), color);
+            if let Some(collisions) = collisions {
+                let resolution = collisions.resolution();
+                if resolution.x != 0. || resolution.y != 0. {
+                    draw(center, center + Vector3::new(resolution.x, resolution.y, 0.), debug.resolution_color);
+                }
+            }
+        }
+        let color = debug.ray_color;
+        for (start, end, _) in &debug.rays {
+            draw(point(start), point(end), color);
+        }
+        debug.drawn = drawn;
+        debug.rays.retain(|(_, _, frames)| *frames > 1);
+        for (_, _, frames) in &mut debug.rays {
+            *frames -= 1;
+        }
+    }
+}
@@ -1,25 +1,230 @@
-use amethyst::core::ecs::prelude::*;
-use amethyst::core::transform::Transform;
-use amethyst::core::math::Vector2;
+use specs::prelude::*;
+#[cfg(feature = "amethyst")]
+use amethyst::{assets::{AssetStorage, Handle}, core::transform::Transform};
+use nalgebra::Vector2;
 
 use smallvec::SmallVec;
 
+use std::collections::HashMap;
 use std::marker::PhantomData;
 
 use crate::shape::Shape;
+#[cfg(feature = "amethyst")]
+use crate::shape::ShapeAsset;
+use crate::pose::Pose;
 use crate::lines::CollisionVector;
+use super::world::{CollisionWorld, Layers, Sensor, ALL_LAYERS};
 
-pub struct ShapeSync;
+/// Entities whose pose component changed, or whose shape was just added, since the last read.
+#[derive(Default)]
+struct Changes {
+    pose_reader: Option<ReaderId<ComponentEvent>>,
+    shape_reader: Option<ReaderId<ComponentEvent>>,
+    dirty: BitSet,
+}
+impl Changes {
+    fn setup<P: Component>(&mut self, world: &mut World) where P::Storage: Tracked {
+        self.pose_reader = Some(WriteStorage::<P>::fetch(world).register_reader());
+        self.shape_reader = Some(WriteStorage::<Shape>::fetch(world).register_reader());
+    }
+    fn read<P: Component>(&mut self, poses: &ReadStorage<P>, shapes: &WriteStorage<Shape>) -> &BitSet
+    where P::Storage: Tracked {
+        self.dirty.clear();
+        let pose_reader = self.pose_reader.as_mut().expect("setup was not called");
+        for event in poses.channel().read(pose_reader) {
+            match *event {
+                ComponentEvent::Inserted(id) | ComponentEvent::Modified(id) => {
+                    self.dirty.add(id);
+                }
+                ComponentEvent::Removed(_) => (),
+            }
+        }
+        let shape_reader = self.shape_reader.as_mut().expect("setup was not called");
+        for event in shapes.channel().read(shape_reader) {
+            if let ComponentEvent::Inserted(id) = *event {
+                self.dirty.add(id);
+            }
+        }
+        &self.dirty
+    }
+}
+
+/// Which matrix of a `Transform` shapes are moved by.
+#[cfg(feature = "amethyst")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SyncSpace {
+    /// The local matrix, ignoring any `Parent`.
+    #[default]
+    Local,
+    /// The world space matrix, for shapes on child entities. `ShapeSync` must run after amethyst's `transform_system`.
+    Global,
+}
+#[cfg(feature = "amethyst")]
+impl SyncSpace {
+    fn apply(self, shape: &mut Shape, transform: &Transform) {
+        match self {
+            SyncSpace::Local => shape.set_transformation(transform),
+            SyncSpace::Global => shape.set_global_transformation(transform),
+        }
+    }
+}
+
+/// Re-transforms the shapes whose `Transform` changed since the last run, and newly added shapes.
+///
+/// Entities with a `Handle<ShapeAsset>` get their `Shape` from the asset once it is loaded,
+/// and again every time it is hot reloaded.
+#[cfg(feature = "amethyst")]
+#[derive(Default)]
+pub struct ShapeSync {
+    space: SyncSpace,
+    changes: Changes,
+    versions: HashMap<Entity, u32>,
+}
+#[cfg(feature = "amethyst")]
+impl ShapeSync {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_space(space: SyncSpace) -> Self {
+        ShapeSync {
+            space,
+            ..Self::default()
+        }
+    }
+}
+#[cfg(feature = "amethyst")]
 impl<'a> System<'a> for ShapeSync {
-    type SystemData = (ReadStorage<'a, Transform>, WriteStorage<'a, Shape>);
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Transform>,
+        WriteStorage<'a, Shape>,
+        ReadStorage<'a, Handle<ShapeAsset>>,
+        Read<'a, AssetStorage<ShapeAsset>>,
+    );
+
+    fn run(&mut self, (entities, transforms, mut shapes, handles, assets): Self::SystemData) {
+        self.versions.retain(|entity, _| handles.contains(*entity));
+        for (entity, handle) in (&entities, &handles).join() {
+            let (asset, version) = match assets.get_with_version(handle) {
+                Some(loaded) => loaded,
+                None => continue,
+            };
+            if self.versions.insert(entity, *version) == Some(*version) && shapes.contains(entity) {
+                continue;
+            }
+            let mut shape = asset.shape().clone();
+            if let Some(transform) = transforms.get(entity) {
+                self.space.apply(&mut shape, transform);
+            }
+            shapes.insert(entity, shape).expect("joined entities are alive");
+        }
+        let dirty = self.changes.read(&transforms, &shapes);
+        for (_, transform, shape) in (dirty, &transforms, &mut shapes).join() {
+            self.space.apply(shape, transform);
+        }
+    }
+
+    fn setup(&mut self, world: &mut World) {
+        Self::SystemData::setup(world);
+        self.changes.setup::<Transform>(world);
+    }
+}
+
+/// Moves the shapes whose `Pose` changed since the last run, and newly added shapes.
+/// This is the `ShapeSync` for plain specs, without amethyst's `Transform`.
+#[derive(Default)]
+pub struct PoseSync {
+    changes: Changes,
+}
+impl PoseSync {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+impl<'a> System<'a> for PoseSync {
+    type SystemData = (ReadStorage<'a, Pose>, WriteStorage<'a, Shape>);
+
+    fn run(&mut self, (poses, mut shapes): Self::SystemData) {
+        let dirty = self.changes.read(&poses, &shapes);
+        for (_, pose, shape) in (dirty, &poses, &mut shapes).join() {
+            shape.set_pose(pose);
+        }
+    }
+
+    fn setup(&mut self, world: &mut World) {
+        Self::SystemData::setup(world);
+        self.changes.setup::<Pose>(world);
+    }
+}
 
-    fn run(&mut self, (transforms, mut shapes): Self::SystemData) {
-        for (transform, shape) in (&transforms, &mut shapes).join() {
-            shape.set_transformation(transform);
+/// Keeps the `CollisionWorld` resource up to date with the shapes that changed. Run it after `ShapeSync` or `PoseSync`.
+#[derive(Default)]
+pub struct WorldSync {
+    shape_reader: Option<ReaderId<ComponentEvent>>,
+    layer_reader: Option<ReaderId<ComponentEvent>>,
+    dirty: BitSet,
+    removed: BitSet,
+    keys: HashMap<u32, Entity>,
+}
+impl WorldSync {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+impl<'a> System<'a> for WorldSync {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Shape>,
+        ReadStorage<'a, Layers>,
+        Write<'a, CollisionWorld<Entity>>,
+    );
+
+    fn run(&mut self, (entities, shapes, layers, mut world): Self::SystemData) {
+        self.dirty.clear();
+        self.removed.clear();
+        let shape_reader = self.shape_reader.as_mut().expect("WorldSync::setup was not called");
+        for event in shapes.channel().read(shape_reader) {
+            match *event {
+                ComponentEvent::Inserted(id) | ComponentEvent::Modified(id) => {
+                    self.dirty.add(id);
+                }
+                ComponentEvent::Removed(id) => {
+                    self.dirty.remove(id);
+                    self.removed.add(id);
+                }
+            }
+        }
+        let layer_reader = self.layer_reader.as_mut().expect("WorldSync::setup was not called");
+        for event in layers.channel().read(layer_reader) {
+            match *event {
+                ComponentEvent::Inserted(id) | ComponentEvent::Modified(id) | ComponentEvent::Removed(id) => {
+                    self.dirty.add(id);
+                }
+            }
+        }
+        for id in (&self.removed).join() {
+            if let Some(entity) = self.keys.remove(&id) {
+                world.remove(entity);
+            }
+        }
+        for (entity, shape, layer, _) in (&entities, &shapes, layers.maybe(), &self.dirty).join() {
+            if let Some(old) = self.keys.insert(entity.id(), entity) {
+                if old != entity {
+                    world.remove(old);
+                }
+            }
+            world.insert(entity, shape.clone(), layer.map_or(ALL_LAYERS, |layer| layer.0));
         }
     }
+
+    fn setup(&mut self, world: &mut World) {
+        Self::SystemData::setup(world);
+        self.shape_reader = Some(WriteStorage::<Shape>::fetch(world).register_reader());
+        self.layer_reader = Some(WriteStorage::<Layers>::fetch(world).register_reader());
+    }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Collisions<T> {
     aggregate: Vector2<f32>,
     collisions: SmallVec<[(CollisionVector, T); 1]>
@@ -48,21 +253,54 @@ impl<T> Default for Collisions<T> {
     }
 }
 
+/// Narrow phase over every pair of entities with a `Shape` and a `T`. When `WorldSync` runs before it and
+/// fills the `CollisionWorld`, only the pairs whose bounding boxes overlap in the world are tested;
+/// otherwise every pair is.
+/// Entities are processed in parallel; each `Collisions` lists its contacts in entity order.
 #[derive(Default)]
 pub struct ColliderSys<T>{
+    layered: bool,
     dud: PhantomData<T>
 }
 impl<'a, T> System<'a> for ColliderSys<T> where
 T: 'static + Send + Sync + Component + Clone {
-    type SystemData = (ReadStorage<'a, Shape>, WriteStorage<'a, Collisions<T>>, Entities<'a>, ReadStorage<'a, T>);
+    type SystemData = (
+        ReadStorage<'a, Shape>,
+        WriteStorage<'a, Collisions<T>>,
+        Entities<'a>,
+        ReadStorage<'a, T>,
+        ReadStorage<'a, Sensor>,
+        ReadStorage<'a, Layers>,
+        Read<'a, CollisionWorld<Entity>>,
+    );
 
-    fn run(&mut self, (shapes, mut collisions, entities, tags): Self::SystemData) {
-        for (shape_a, collision_out, id_a) in (&shapes, &mut collisions, &entities).join() {
+    fn run(&mut self, (shapes, mut collisions, entities, tags, sensors, layers, world): Self::SystemData) {
+        let layer_of = |id: Entity| match self.layered {
+            true => layers.get(id).map_or(ALL_LAYERS, |layers| layers.0),
+            false => ALL_LAYERS,
+        };
+        (&shapes, &mut collisions, &entities).par_join().for_each(|(shape_a, collision_out, id_a)| {
+            let mask = layer_of(id_a);
+            let pairs = if world.is_empty() {
+                (&shapes, &entities).join()
+                    .map(|(_, id_b)| id_b)
+                    .filter(|id_b| layer_of(*id_b) & mask != 0)
+                    .collect()
+            } else {
+                let mut pairs = world.query_aabb(&shape_a.aabb().expanded(world.tolerance()), mask);
+                pairs.sort();
+                pairs
+            };
             let mut record = SmallVec::new();
             let mut aggregate = Vector2::new(0., 0.);
-            for (shape_b, _id_b, tag) in (&shapes, &entities, &tags).join().filter(|(_, id, _)| *id != id_a) {
-                if let Some(vector) = shape_a.collide(shape_b) {
-                    if let CollisionVector::Resolve(resolution) = vector {
+            for id_b in pairs.into_iter().filter(|id| *id != id_a) {
+                let (shape_b, tag) = match (shapes.get(id_b), tags.get(id_b)) {
+                    (Some(shape_b), Some(tag)) => (shape_b, tag),
+                    _ => continue,
+                };
+                if let Some(vector) = shape_a.collide_with_tolerance(shape_b, world.tolerance()) {
+                    let sensor = sensors.contains(id_a) || sensors.contains(id_b);
+                    if let (CollisionVector::Resolve(resolution), false) = (&vector, sensor) {
                         aggregate += resolution;
                     }
                     record.push((vector, tag.clone()));
@@ -72,13 +310,21 @@ T: 'static + Send + Sync + Component + Clone {
                 aggregate,
                 collisions: record,
             }
-        }
+        });
     }
 }
 impl<T> ColliderSys<T> {
     pub fn new() -> Self {
         ColliderSys {
+            layered: false,
             dud: PhantomData
         }
     }
-}
\ No newline at end of file
+    /// Only pairs entities sharing one of their `Layers`; entities without the component are on every layer.
+    pub fn with_layers() -> Self {
+        ColliderSys {
+            layered: true,
+            dud: PhantomData
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use crate::shape::{GenericAabb, GenericCollisionVector, GenericShape};
+use nalgebra::{Point2, Vector2};
+#[cfg(feature = "specs")]
+use specs::{storage::{DenseVecStorage, FlaggedStorage, NullStorage}, Component};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::sync::{Mutex, MutexGuard};
+use crate::scalar::Real;
+
+pub const ALL_LAYERS: u32 = !0;
+
+/// Layer membership of a shape. Queries only see shapes sharing a bit with their mask.
+/// Shapes without this component are on every layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Layers(pub u32);
+impl Default for Layers {
+    fn default() -> Self {
+        Layers(ALL_LAYERS)
+    }
+}
+#[cfg(feature = "specs")]
+impl Component for Layers {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
+
+/// Marks a trigger shape. Its contacts are still listed in `Collisions`, but never added to the resolution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sensor;
+#[cfg(feature = "specs")]
+impl Component for Sensor {
+    type Storage = NullStorage<Self>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WorldHit<K, T: Real = f32> {
+    pub key: K,
+    pub point: Point2<T>,
+    pub distance: T,
+}
+
+#[derive(Debug, Clone)]
+struct Entry<T: Real> {
+    shape: GenericShape<T>,
+    aabb: GenericAabb<T>,
+    layers: u32,
+}
+
+/// Start and end of every logged ray, or `None` while logging is off.
+type RayLog<T> = Option<Vec<(Point2<T>, Point2<T>)>>;
+
+/// Spatially hashed copy of every shape, kept up to date by `WorldSync`.
+pub struct CollisionWorld<K, T: Real = f32> {
+    cell_size: T,
+    tolerance: T,
+    entries: HashMap<K, Entry<T>>,
+    cells: HashMap<(i32, i32), Vec<K>>,
+    bounds: Option<GenericAabb<T>>,
+    rays: Mutex<RayLog<T>>,
+}
+impl<K: Copy + Eq + Hash, T: Real> CollisionWorld<K, T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Panics unless `cell_size` is positive: zero, negative and NaN sizes can't hash a point to a cell.
+    pub fn with_cell_size(cell_size: T) -> Self {
+        assert!(cell_size > T::zero(), "the cell size of a CollisionWorld must be positive, not {}", cell_size.to_f64());
+        CollisionWorld {
+            cell_size,
+            tolerance: T::zero(),
+            entries: HashMap::new(),
+            cells: HashMap::new(),
+            bounds: None,
+            rays: Mutex::new(None),
+        }
+    }
+    /// Contact tolerance used by `overlap` and the collision systems.
+    pub fn tolerance(&self) -> T {
+        self.tolerance
+    }
+    /// Shapes closer than `tolerance` are reported as touching, see `Shape::collide_with_tolerance`.
+    pub fn set_tolerance(&mut self, tolerance: T) {
+        self.tolerance = tolerance;
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    pub fn get(&self, key: K) -> Option<&GenericShape<T>> {
+        Some(&self.entries.get(&key)?.shape)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (K, &GenericShape<T>)> {
+        self.entries.iter().map(|(key, entry)| (*key, &entry.shape))
+    }
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.cells.clear();
+        self.bounds = None;
+    }
+    pub fn insert(&mut self, key: K, shape: GenericShape<T>, layers: u32) -> Option<GenericShape<T>> {
+        let old = self.remove(key);
+        let aabb = shape.aabb();
+        for cell in self.cells_in(&aabb) {
+            self.cells.entry(cell).or_default().push(key);
+        }
+        self.bounds = Some(match self.bounds {
+            Some(bounds) => bounds.merged(&aabb),
+            None => aabb,
+        });
+        self.entries.insert(key, Entry { shape, aabb, layers });
+        old
+    }
+    pub fn remove(&mut self, key: K) -> Option<GenericShape<T>> {
+        let entry = self.entries.remove(&key)?;
+        for cell in self.cells_in(&entry.aabb) {
+            if let Some(keys) = self.cells.get_mut(&cell) {
+                keys.retain(|other| *other != key);
+                if keys.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+        Some(entry.shape)
+    }
+
+    /// Keys of the shapes whose bounding boxes overlap `aabb`.
+    pub fn query_aabb(&self, aabb: &GenericAabb<T>, mask: u32) -> Vec<K> {
+        self.candidates(self.cells_in(aabb), mask)
+            .filter(|(_, entry)| entry.aabb.intersects(aabb))
+            .map(|(key, _)| key)
+            .collect()
+    }
+    /// Keys of the shapes containing `point`.
+    pub fn query_point(&self, point: Point2<T>, mask: u32) -> Vec<K> {
+        self.candidates(Some(self.cell_of(point)), mask)
+            .filter(|(_, entry)| entry.shape.contains(point))
+            .map(|(key, _)| key)
+            .collect()
+    }
+    /// Every shape colliding with `shape`, with the vector resolving `shape` out of it.
+    pub fn overlap(&self, shape: &GenericShape<T>, mask: u32) -> Vec<(K, GenericCollisionVector<T>)> {
+        let aabb = shape.aabb().expanded(self.tolerance);
+        self.candidates(self.cells_in(&aabb), mask)
+            .filter(|(_, entry)| entry.aabb.intersects(&aabb))
+            .filter_map(|(key, entry)| Some((key, shape.collide_with_tolerance(&entry.shape, self.tolerance)?)))
+            .collect()
+    }
+    /// Starts or stops logging the rays cast through `raycast` and `raycast_first`, for `DebugLinesSys`.
+    /// Stopping drops the rays not taken yet.
+    pub fn set_ray_log(&self, enabled: bool) {
+        let mut rays = self.ray_log();
+        match (enabled, rays.is_some()) {
+            (true, false) => *rays = Some(Vec::new()),
+            (false, _) => *rays = None,
+            _ => (),
+        }
+    }
+    /// Rays logged since the last call, from their start to their first hit, or to where they leave the world.
+    pub fn take_rays(&self) -> Vec<(Point2<T>, Point2<T>)> {
+        let mut rays = self.ray_log();
+        rays.as_mut().map_or_else(Vec::new, std::mem::take)
+    }
+    /// Every shape hit by the ray, nearest first.
+    pub fn raycast(&self, start: Point2<T>, angle: T, mask: u32) -> Vec<WorldHit<K, T>> {
+        let (sin, cos) = angle.sin_cos();
+        let direction = Vector2::new(cos, sin);
+        let mut hits: Vec<_> = self
+            .candidates(self.ray_cells(start, direction), mask)
+            .filter_map(|(key, entry)| {
+                let distance = entry.shape.cast_ray(start, direction)?;
+                Some(WorldHit {
+                    key,
+                    point: start + direction * distance,
+                    distance,
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap_or(std::cmp::Ordering::Equal));
+        let mut rays = self.ray_log();
+        if let Some(rays) = rays.as_mut() {
+            let end = match (hits.first(), self.bounds.and_then(|bounds| bounds.ray_interval(start, direction))) {
+                (Some(hit), _) => hit.point,
+                (None, Some((_, t_exit))) => start + direction * t_exit,
+                (None, None) => start + direction * self.cell_size,
+            };
+            rays.push((start, end));
+        }
+        hits
+    }
+    pub fn raycast_first(&self, start: Point2<T>, angle: T, mask: u32) -> Option<WorldHit<K, T>> {
+        self.raycast(start, angle, mask).into_iter().next()
+    }
+    /// Sweeps `shape` along `angle` for up to `max_distance` and returns the first shape it would touch.
+    /// Shapes already overlapping `shape` are reported at distance zero.
+    pub fn shape_cast(&self, shape: &GenericShape<T>, angle: T, max_distance: T, mask: u32) -> Option<WorldHit<K, T>> {
+        let (sin, cos) = angle.sin_cos();
+        let direction = Vector2::new(cos, sin);
+        let start = shape.aabb();
+        let swept = start.merged(&start.translated(direction * max_distance));
+        self.candidates(self.cells_in(&swept), mask)
+            .filter(|(_, entry)| entry.aabb.intersects(&swept))
+            .filter_map(|(key, entry)| {
+                if shape.collide_with_tolerance(&entry.shape, self.tolerance).is_some() {
+                    return Some(WorldHit {
+                        key,
+                        point: shape.centroid(),
+                        distance: T::zero(),
+                    });
+                }
+                let forward = shape.iter_points().filter_map(|&point| {
+                    let distance = entry.shape.cast_ray(point, direction)?;
+                    Some((point + direction * distance, distance))
+                });
+                let backward = entry.shape.iter_points().filter_map(|&point| {
+                    Some((point, shape.cast_ray(point, -direction)?))
+                });
+                forward
+                    .chain(backward)
+                    .filter(|(_, distance)| *distance <= max_distance)
+                    .fold(None, |prev: Option<WorldHit<K, T>>, (point, distance)| match prev {
+                        Some(prev) if prev.distance <= distance => Some(prev),
+                        _ => Some(WorldHit { key, point, distance }),
+                    })
+            })
+            .fold(None, |prev, hit| match prev {
+                Some(prev) if prev.distance <= hit.distance => Some(prev),
+                _ => Some(hit),
+            })
+    }
+
+    fn ray_log(&self) -> MutexGuard<'_, RayLog<T>> {
+        // The log holds nothing a panicking raycast could leave half written.
+        self.rays.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+    fn candidates<'a, C: IntoIterator<Item = (i32, i32)> + 'a>(
+        &'a self,
+        cells: C,
+        mask: u32,
+    ) -> impl Iterator<Item = (K, &'a Entry<T>)> + 'a {
+        let mut seen = HashSet::new();
+        cells
+            .into_iter()
+            .filter_map(move |cell| self.cells.get(&cell))
+            .flatten()
+            .filter(move |key| seen.insert(**key))
+            .filter_map(move |key| Some((*key, self.entries.get(key)?)))
+            .filter(move |(_, entry)| entry.layers & mask != 0)
+    }
+    fn cell_of(&self, point: Point2<T>) -> (i32, i32) {
+        (
+            (point.x / self.cell_size).floor().to_f64() as i32,
+            (point.y / self.cell_size).floor().to_f64() as i32,
+        )
+    }
+    fn cells_in(&self, aabb: &GenericAabb<T>) -> impl Iterator<Item = (i32, i32)> {
+        let (min_x, min_y) = self.cell_of(aabb.min);
+        let (max_x, max_y) = self.cell_of(aabb.max);
+        (min_x..=max_x).flat_map(move |x| (min_y..=max_y).map(move |y| (x, y)))
+    }
+    /// Cells crossed by a ray, in order, clipped to the bounds of the world. When the ray would cross more cells
+    /// than hold shapes, the occupied cells are returned instead, in no particular order.
+    fn ray_cells(&self, start: Point2<T>, direction: Vector2<T>) -> Vec<(i32, i32)> {
+        let bounds = match self.bounds {
+            Some(bounds) => bounds,
+            None => return Vec::new(),
+        };
+        let (t_enter, t_exit) = match bounds.ray_interval(start, direction) {
+            Some(interval) => interval,
+            None => return Vec::new(),
+        };
+        let entry = start + direction * t_enter;
+        let (mut x, mut y) = self.cell_of(entry);
+        let (end_x, end_y) = self.cell_of(start + direction * t_exit);
+        let step = |d: T| if d > T::zero() { 1 } else { -1 };
+        let delta = |d: T| if d != T::zero() { self.cell_size / d.abs() } else { T::max_value() };
+        let first_crossing = |cell: i32, pos: T, d: T| {
+            if d > T::zero() {
+                (T::from_f64((cell + 1) as f64) * self.cell_size - pos) / d
+            } else if d < T::zero() {
+                (T::from_f64(cell as f64) * self.cell_size - pos) / d
+            } else {
+                T::max_value()
+            }
+        };
+        let (step_x, step_y) = (step(direction.x), step(direction.y));
+        let (delta_x, delta_y) = (delta(direction.x), delta(direction.y));
+        let mut next_x = first_crossing(x, entry.x, direction.x);
+        let mut next_y = first_crossing(y, entry.y, direction.y);
+        let count = (end_x as i64 - x as i64).abs() + (end_y as i64 - y as i64).abs() + 1;
+        if count > self.cells.len() as i64 {
+            return self.cells.keys().copied().collect();
+        }
+        let mut cells = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            cells.push((x, y));
+            if next_x < next_y {
+                x = x.saturating_add(step_x);
+                next_x += delta_x;
+            } else {
+                y = y.saturating_add(step_y);
+                next_y += delta_y;
+            }
+        }
+        cells
+    }
+}
+impl<K: Copy + Eq + Hash, T: Real> Default for CollisionWorld<K, T> {
+    fn default() -> Self {
+        Self::with_cell_size(T::from_f64(64.))
+    }
+}
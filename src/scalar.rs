@@ -0,0 +1,114 @@
+use nalgebra::Vector2;
+use num_traits::{One, Zero};
+use std::fmt::Debug;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+mod fixed;
+pub use fixed::Fixed;
+
+/// Number type the geometry is generic over. `f32` is the default everywhere.
+///
+/// Only needs arithmetic and a handful of functions, so it is not tied to floats.
+pub trait Real:
+    Copy
+    + PartialEq
+    + PartialOrd
+    + Debug
+    + Send
+    + Sync
+    + 'static
+    + Zero
+    + One
+    + Add<Output = Self>
+    + AddAssign
+    + Sub<Output = Self>
+    + SubAssign
+    + Mul<Output = Self>
+    + MulAssign
+    + Div<Output = Self>
+    + DivAssign
+    + Neg<Output = Self>
+{
+    fn from_f64(value: f64) -> Self;
+    fn to_f64(self) -> f64;
+    fn sqrt(self) -> Self;
+    fn floor(self) -> Self;
+    fn sin_cos(self) -> (Self, Self);
+    fn atan2(self, x: Self) -> Self;
+    /// Largest finite value, standing in for infinity.
+    fn max_value() -> Self;
+    /// Length of the vector `(self, y)`.
+    fn hypot(self, y: Self) -> Self {
+        (self * self + y * y).sqrt()
+    }
+    /// `ax * by - ay * bx`, the 2D cross product of `a` and `b`.
+    fn cross(ax: Self, ay: Self, bx: Self, by: Self) -> Self {
+        ax * by - ay * bx
+    }
+    fn pi() -> Self {
+        Self::from_f64(std::f64::consts::PI)
+    }
+    fn abs(self) -> Self {
+        if self < Self::zero() {
+            -self
+        } else {
+            self
+        }
+    }
+    fn min(self, other: Self) -> Self {
+        if other < self {
+            other
+        } else {
+            self
+        }
+    }
+    fn max(self, other: Self) -> Self {
+        if other > self {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+macro_rules! float_real {
+    ($t:ty) => {
+        impl Real for $t {
+            fn from_f64(value: f64) -> Self {
+                value as $t
+            }
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+            fn sqrt(self) -> Self {
+                <$t>::sqrt(self)
+            }
+            fn floor(self) -> Self {
+                <$t>::floor(self)
+            }
+            fn sin_cos(self) -> (Self, Self) {
+                <$t>::sin_cos(self)
+            }
+            fn atan2(self, x: Self) -> Self {
+                <$t>::atan2(self, x)
+            }
+            fn max_value() -> Self {
+                <$t>::MAX
+            }
+            fn abs(self) -> Self {
+                <$t>::abs(self)
+            }
+        }
+    };
+}
+float_real!(f32);
+float_real!(f64);
+
+pub fn magnitude<T: Real>(v: &Vector2<T>) -> T {
+    v.x.hypot(v.y)
+}
+
+/// 2D cross product, positive when `b` is counter-clockwise of `a`.
+pub fn cross<T: Real>(a: &Vector2<T>, b: &Vector2<T>) -> T {
+    T::cross(a.x, a.y, b.x, b.y)
+}
@@ -0,0 +1,229 @@
+use num_traits::{One, Zero};
+use std::fmt::{Debug, Display, Error, Formatter};
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use super::Real;
+
+const FRAC_BITS: u32 = 32;
+const ONE_BITS: i64 = 1 << FRAC_BITS;
+const PI_BITS: i128 = 13_493_037_705;
+const HALF_PI_BITS: i128 = PI_BITS / 2;
+/// Product of the CORDIC gains, so the rotation comes out unscaled.
+const CORDIC_GAIN: i128 = 2_608_131_496;
+/// `atan(2^-i)` for every CORDIC step.
+const ATAN_TABLE: [i128; 32] = [
+    3_373_259_426, 1_991_351_318, 1_052_175_346, 534_100_635,
+    268_086_748, 134_174_063, 67_103_403, 33_553_749,
+    16_777_131, 8_388_597, 4_194_303, 2_097_152,
+    1_048_576, 524_288, 262_144, 131_072,
+    65_536, 32_768, 16_384, 8_192,
+    4_096, 2_048, 1_024, 512,
+    256, 128, 64, 32,
+    16, 8, 4, 2,
+];
+
+/// 32.32 fixed point number for lockstep simulations.
+///
+/// Everything, including `sqrt`, `sin_cos` and `atan2`, is integer arithmetic,
+/// so the same inputs give bit-identical results on every platform.
+/// Operations saturate instead of overflowing.
+///
+/// Values range over ±2^31, about ±2.1e9, but a product saturates once it leaves that range, so
+/// a plain `x * x` goes wrong for `x` above about 46341. `hypot` and `cross` compute their products
+/// in 128 bits, so lengths and cross products stay exact as long as the result itself fits. Keep the
+/// areas and dot products the geometry takes of shape edges and ray offsets below 2^31 as well:
+/// shapes can sit anywhere in range, but a single shape or ray should span less than about 46000 units.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fixed(i64);
+
+impl Fixed {
+    pub const fn from_bits(bits: i64) -> Fixed {
+        Fixed(bits)
+    }
+    pub const fn to_bits(self) -> i64 {
+        self.0
+    }
+    pub const fn from_int(value: i32) -> Fixed {
+        Fixed((value as i64) << FRAC_BITS)
+    }
+    fn saturate(bits: i128) -> Fixed {
+        Fixed(bits.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+}
+
+impl From<i32> for Fixed {
+    fn from(value: i32) -> Fixed {
+        Fixed::from_int(value)
+    }
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+    fn add(self, other: Fixed) -> Fixed {
+        Fixed(self.0.saturating_add(other.0))
+    }
+}
+impl AddAssign for Fixed {
+    fn add_assign(&mut self, other: Fixed) {
+        *self = *self + other
+    }
+}
+impl Sub for Fixed {
+    type Output = Fixed;
+    fn sub(self, other: Fixed) -> Fixed {
+        Fixed(self.0.saturating_sub(other.0))
+    }
+}
+impl SubAssign for Fixed {
+    fn sub_assign(&mut self, other: Fixed) {
+        *self = *self - other
+    }
+}
+impl Mul for Fixed {
+    type Output = Fixed;
+    fn mul(self, other: Fixed) -> Fixed {
+        Fixed::saturate((self.0 as i128 * other.0 as i128) >> FRAC_BITS)
+    }
+}
+impl MulAssign for Fixed {
+    fn mul_assign(&mut self, other: Fixed) {
+        *self = *self * other
+    }
+}
+impl Div for Fixed {
+    type Output = Fixed;
+    fn div(self, other: Fixed) -> Fixed {
+        if other.0 == 0 {
+            return match self.0 {
+                0 => Fixed(0),
+                bits if bits > 0 => Fixed(i64::MAX),
+                _ => Fixed(i64::MIN),
+            };
+        }
+        Fixed::saturate(((self.0 as i128) << FRAC_BITS) / other.0 as i128)
+    }
+}
+impl DivAssign for Fixed {
+    fn div_assign(&mut self, other: Fixed) {
+        *self = *self / other
+    }
+}
+impl Neg for Fixed {
+    type Output = Fixed;
+    fn neg(self) -> Fixed {
+        Fixed(self.0.saturating_neg())
+    }
+}
+
+impl Zero for Fixed {
+    fn zero() -> Fixed {
+        Fixed(0)
+    }
+    fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+}
+impl One for Fixed {
+    fn one() -> Fixed {
+        Fixed(ONE_BITS)
+    }
+}
+
+impl Real for Fixed {
+    fn from_f64(value: f64) -> Fixed {
+        Fixed((value * ONE_BITS as f64).round() as i64)
+    }
+    fn to_f64(self) -> f64 {
+        self.0 as f64 / ONE_BITS as f64
+    }
+    fn sqrt(self) -> Fixed {
+        if self.0 <= 0 {
+            return Fixed(0);
+        }
+        Fixed(((self.0 as u128) << FRAC_BITS).isqrt() as i64)
+    }
+    fn floor(self) -> Fixed {
+        Fixed(self.0 & !(ONE_BITS - 1))
+    }
+    fn sin_cos(self) -> (Fixed, Fixed) {
+        let mut z = (self.0 as i128).rem_euclid(2 * PI_BITS);
+        if z > PI_BITS {
+            z -= 2 * PI_BITS;
+        }
+        // CORDIC only converges within a quarter turn, so rotate the rest by half a turn.
+        let flip = !(-HALF_PI_BITS..=HALF_PI_BITS).contains(&z);
+        if z > HALF_PI_BITS {
+            z -= PI_BITS;
+        } else if z < -HALF_PI_BITS {
+            z += PI_BITS;
+        }
+        let (mut x, mut y) = (CORDIC_GAIN, 0);
+        for (i, step) in ATAN_TABLE.iter().enumerate() {
+            let (dx, dy) = (y >> i, x >> i);
+            if z >= 0 {
+                x -= dx;
+                y += dy;
+                z -= step;
+            } else {
+                x += dx;
+                y -= dy;
+                z += step;
+            }
+        }
+        if flip {
+            (Fixed::saturate(-y), Fixed::saturate(-x))
+        } else {
+            (Fixed::saturate(y), Fixed::saturate(x))
+        }
+    }
+    fn atan2(self, x: Fixed) -> Fixed {
+        let (mut x, mut y) = (x.0 as i128, self.0 as i128);
+        if x == 0 && y == 0 {
+            return Fixed(0);
+        }
+        let mut z = 0;
+        if x < 0 {
+            z = if y >= 0 { PI_BITS } else { -PI_BITS };
+            x = -x;
+            y = -y;
+        }
+        for (i, step) in ATAN_TABLE.iter().enumerate() {
+            let (dx, dy) = (y >> i, x >> i);
+            if y > 0 {
+                x += dx;
+                y -= dy;
+                z += step;
+            } else {
+                x -= dx;
+                y += dy;
+                z -= step;
+            }
+        }
+        Fixed::saturate(z.clamp(-PI_BITS, PI_BITS))
+    }
+    fn max_value() -> Fixed {
+        Fixed(i64::MAX)
+    }
+    fn hypot(self, y: Fixed) -> Fixed {
+        let square = |bits: i64| (bits.unsigned_abs() as u128).pow(2);
+        Fixed((square(self.0) + square(y.0)).isqrt().min(i64::MAX as u128) as i64)
+    }
+    fn cross(ax: Fixed, ay: Fixed, bx: Fixed, by: Fixed) -> Fixed {
+        let wide = (ax.0 as i128 * by.0 as i128).saturating_sub(ay.0 as i128 * bx.0 as i128);
+        Fixed::saturate(wide >> FRAC_BITS)
+    }
+    fn pi() -> Fixed {
+        Fixed(PI_BITS as i64)
+    }
+}
+
+impl Display for Fixed {
+    fn fmt(&self, form: &mut Formatter) -> Result<(), Error> {
+        write!(form, "{}", self.to_f64())
+    }
+}
+impl Debug for Fixed {
+    fn fmt(&self, form: &mut Formatter) -> Result<(), Error> {
+        write!(form, "Fixed({})", self.to_f64())
+    }
+}
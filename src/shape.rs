@@ -1,102 +1,135 @@
 mod shapeiters;
+mod bounds;
+mod validate;
+mod hull;
+mod decompose;
+mod simplify;
+mod primitives;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "amethyst")]
+mod prefab;
+#[cfg(feature = "amethyst")]
+mod asset;
 use crate::lines::*;
+use crate::pose::GenericPose;
+use crate::scalar::{magnitude, Real};
 use shapeiters::*;
-use std::f32;
 use std::slice::Iter;
 use std::borrow::Borrow;
 use std::cmp::PartialEq;
-use amethyst::{
-    core::{
-        math::{Point2, Vector2, Point4},
-        transform::Transform
-    },
-    assets::{PrefabData},
-    ecs::{
-        storage::DenseVecStorage,
-        Component,
-        Entity,
-        WriteStorage
-    },
-    Error
-};
-use serde::{Serialize, Deserialize};
+use nalgebra::{Matrix4, Point2, Vector2, Point4};
+#[cfg(feature = "specs")]
+use specs::{storage::{DenseVecStorage, FlaggedStorage}, Component};
 
-pub use crate::lines::CollisionVector;
+pub use crate::lines::{CollisionVector, GenericCollisionVector, GenericInEq, GenericLine, InEq, Line};
+pub use bounds::{Aabb, GenericAabb};
+pub use validate::ShapeError;
+pub use simplify::{ramer_douglas_peucker, visvalingam_whyatt};
+#[cfg(feature = "amethyst")]
+pub use prefab::{ShapeKind, ShapePrefab};
+#[cfg(feature = "amethyst")]
+pub use asset::ShapeAsset;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ShapePrefab {
-    pub points: Vec<Point2<f32>>
-}
-impl<'a> PrefabData<'a> for ShapePrefab {
-    type SystemData = WriteStorage<'a, Shape>;
-
-    type Result = ();
-
-    fn add_to_entity(
-        &self,
-        entity: Entity,
-        shapes: &mut Self::SystemData,
-        _entities: &[Entity],
-        _children: &[Entity],
-    ) -> Result<(), Error> {
-        shapes.insert(entity, Shape::new(self.points.clone())).map(|_| ())?;
-        Ok(())
-    }
-}
+/// A convex polygon collider over any scalar. Most code uses the `f32` alias `Shape`;
+/// large maps can use `GenericShape<f64>` and lockstep games `GenericShape<Fixed>`.
 #[derive(Debug, Clone)]
-pub struct Shape {
-    points: Vec<Point2<f32>>,
-    moved_points: Vec<Point2<f32>>,
-    center: Point2<f32>,
-    moved_center: Point2<f32>,
+pub struct GenericShape<T: Real> {
+    points: Vec<Point2<T>>,
+    moved_points: Vec<Point2<T>>,
+    center: Point2<T>,
+    moved_center: Point2<T>,
 }
-impl Shape {
-    pub fn new<PointType: Borrow<Point2<f32>>, I: IntoIterator<Item=PointType>>(points: I) -> Shape {
-        let mut avg = Vector2::new(0., 0.);
-        let mut iter = points.into_iter().peekable();
-        let size = if iter.size_hint().0 != 0 {
-            iter.size_hint().0 * 2
-        } else {
-            8
-        };
-        let first = match iter.peek() {
-            Some(value) => Vector2::new(value.borrow().x, value.borrow().y),
-            None => return Shape {
-                points: Vec::new(),
-                moved_points: Vec::new(),
-                moved_center: Point2::new(0. ,0.),
-                center: Point2::new(0., 0.)
-            }
-        };
-        let mut points = Vec::with_capacity(size);
-        while let Some(point) = iter.next() {
-            let point = point.borrow();
-            let next = match iter.peek() {
-                Some(nxt) => Vector2::new(nxt.borrow().x, nxt.borrow().y),
-                None => first
-            };
-            let mid_mid = point + next;
-            let mid = Point2::new(mid_mid.x/2., mid_mid.y/2.);
-            avg += Vector2::new(point.x, point.y);
-            points.push(*point);
-            points.push(mid);
-            
-        }
-        if points.len() != 0 {
-            avg = avg / (points.len() as f32);
+/// The `f32` collider the ECS integrations store and query.
+pub type Shape = GenericShape<f32>;
+
+impl<T: Real> GenericShape<T> {
+    /// Builds a shape from its outline. Clockwise outlines are reversed, so every shape winds counter-clockwise:
+    /// the first point stays first and the others follow in reverse order, which is the order `iter_points`
+    /// and serialization then use.
+    pub fn new<PointType: Borrow<Point2<T>>, I: IntoIterator<Item=PointType>>(points: I) -> GenericShape<T> {
+        let mut outline: Vec<Point2<T>> = points.into_iter().map(|point| *point.borrow()).collect();
+        if validate::signed_area(&outline) < T::zero() {
+            outline[1..].reverse();
         }
-        let center = Point2::from(avg);
-        Shape {
-            points: points.clone(),
-            moved_points: points,
+        let center = average(&outline);
+        GenericShape {
+            points: outline.clone(),
+            moved_points: outline,
             moved_center: center,
             center
         }
     }
-    pub fn iter_points<'a>(&'a self) -> Iter<'a, Point2<f32>> {
+    /// Like `new`, but rejects point lists that don't form a simple convex polygon.
+    pub fn try_new<PointType: Borrow<Point2<T>>, I: IntoIterator<Item=PointType>>(points: I) -> Result<GenericShape<T>, ShapeError> {
+        let points: Vec<Point2<T>> = points.into_iter().map(|point| *point.borrow()).collect();
+        validate::validate(&points)?;
+        Ok(GenericShape::new(points))
+    }
+    /// Smallest convex shape around every point, for point clouds such as the opaque pixels of a sprite.
+    pub fn convex_hull<PointType: Borrow<Point2<T>>, I: IntoIterator<Item=PointType>>(points: I) -> Result<GenericShape<T>, ShapeError> {
+        let points: Vec<Point2<T>> = points.into_iter().map(|point| *point.borrow()).collect();
+        let count = points.len();
+        match hull::convex_hull(points) {
+            hull if hull.len() < 3 && count >= 3 => Err(ShapeError::DegenerateArea),
+            hull => GenericShape::try_new(hull),
+        }
+    }
+    /// Splits any simple polygon, concave or not, into convex shapes that together cover it.
+    /// Pieces without area are dropped, so a self-intersecting outline may come back incomplete.
+    pub fn convex_decomposition<PointType: Borrow<Point2<T>>, I: IntoIterator<Item=PointType>>(points: I) -> Vec<GenericShape<T>> {
+        let points: Vec<Point2<T>> = points.into_iter().map(|point| *point.borrow()).collect();
+        if validate::validate(&points).is_ok() {
+            return vec![GenericShape::new(points)];
+        }
+        decompose::decompose(&points)
+            .into_iter()
+            .filter_map(|piece| GenericShape::try_new(piece).ok())
+            .collect()
+    }
+    /// Drops the vertices closer than `tolerance` to the outline kept around them, see `ramer_douglas_peucker`.
+    pub fn ramer_douglas_peucker(&self, tolerance: T) -> GenericShape<T> {
+        self.keep(&simplify::ramer_douglas_peucker_mask(&self.points, tolerance))
+    }
+    /// Drops the vertices that add less than `area` to the outline, see `visvalingam_whyatt`.
+    pub fn visvalingam_whyatt(&self, area: T) -> GenericShape<T> {
+        self.keep(&simplify::visvalingam_whyatt_mask(&self.points, area))
+    }
+    fn keep(&self, mask: &[bool]) -> GenericShape<T> {
+        let points = simplify::filter(&self.points, mask);
+        let moved_points = simplify::filter(&self.moved_points, mask);
+        GenericShape {
+            center: average(&points),
+            moved_center: average(&moved_points),
+            points,
+            moved_points,
+        }
+    }
+    /// The vertices moved by the last pose or transform, counter-clockwise. For outlines given clockwise
+    /// this is not the order they were passed to `new` in.
+    pub fn iter_points<'a>(&'a self) -> Iter<'a, Point2<T>> {
         self.moved_points.iter()
     }
-    fn iter_sides<'a>(&'a self) -> SidesIter<'a> {
+    pub fn centroid(&self) -> Point2<T> {
+        self.moved_center
+    }
+    pub fn aabb(&self) -> GenericAabb<T> {
+        GenericAabb::from_points(self.iter_points())
+            .unwrap_or_else(|| GenericAabb::new(self.moved_center, self.moved_center))
+    }
+    fn iter_edges<'a>(&'a self) -> impl Iterator<Item = (Point2<T>, Point2<T>)> + 'a {
+        self.moved_points
+            .iter()
+            .zip(self.moved_points.iter().cycle().skip(1))
+            .map(|(&a, &b)| (a, b))
+    }
+    /// Every vertex followed by the middle of its edge, so edges crossing without a vertex inside still collide.
+    fn iter_samples<'a>(&'a self) -> impl Iterator<Item = Point2<T>> + 'a {
+        let two = T::one() + T::one();
+        self.iter_edges()
+            .flat_map(move |(a, b)| [a, Point2::from((a.coords + b.coords) / two)])
+    }
+    fn iter_sides<'a>(&'a self) -> SidesIter<'a, T> {
         let mut iter = self.iter_points().peekable();
         match iter.peek() {
             Some(&first) => SidesIter {
@@ -106,20 +139,25 @@ impl Shape {
             },
             None => SidesIter {
                 points: iter,
-                center: Point2::new(0., 0.),
-                first: Point2::new(0., 0.),
+                center: Point2::origin(),
+                first: Point2::origin(),
             },
         }
     }
     
-    fn dist_inside(&self, point: Point2<f32>) -> Option<CollisionVector> {
-        let mut out: Option<(CollisionVector, f32)> = None;
+    fn dist_inside(&self, point: Point2<T>, tolerance: T) -> Option<GenericCollisionVector<T>> {
+        let mut out: Option<(GenericCollisionVector<T>, T)> = None;
+        let mut touch = None;
         for side in self.iter_sides() {
-            let dist = side.distance(point)?;
+            let dist = side.distance_within(point, tolerance)?;
             match dist {
-                CollisionVector::Touch(_) => return Some(dist),
-                CollisionVector::Resolve(resolution) => {
-                    let mag = resolution.magnitude();
+                GenericCollisionVector::Touch(_) => {
+                    if touch.is_none() {
+                        touch = Some(dist);
+                    }
+                }
+                GenericCollisionVector::Resolve(resolution) => {
+                    let mag = magnitude(&resolution);
                     match &out {
                         Some(val) => {
                             if mag < val.1 {
@@ -131,42 +169,56 @@ impl Shape {
                 }
             }
         }
-        Some(out?.0)
+        match touch {
+            Some(touch) => Some(touch),
+            None => Some(out?.0),
+        }
+    }
+    pub fn collide(&self, other: &GenericShape<T>) -> Option<GenericCollisionVector<T>> {
+        self.collide_with_tolerance(other, T::zero())
     }
-    pub fn collide(&self, other: &Shape) -> Option<CollisionVector> {
-        let mut result = None;
-        for res in self.iter_points()
-        .filter_map(|point| other.dist_inside(*point))
+    /// Like `collide`, but vertices within `tolerance` of an edge are reported as `Touch`,
+    /// so resting contacts stay stable from frame to frame. A `Touch` is only reported when
+    /// no sample sinks deeper than `tolerance` into the other shape.
+    pub fn collide_with_tolerance(&self, other: &GenericShape<T>, tolerance: T) -> Option<GenericCollisionVector<T>> {
+        let mut deepest: Option<(T, GenericCollisionVector<T>)> = None;
+        let mut touch = None;
+        for res in self.iter_samples()
+        .filter_map(|point| other.dist_inside(point, tolerance))
         .chain(
             other
-                .iter_points()
-                .filter_map(|point| Some(other.dist_inside(*point)?.flip())),
+                .iter_samples()
+                .filter_map(|point| Some(self.dist_inside(point, tolerance)?.flip())),
         ) {
-            match &result {
-                None => result = Some((res.magnitude(), res)),
-                Some((mag, _old_res)) => {
-                    match res {
-                        CollisionVector::Touch(_) => return Some(res),
-                        CollisionVector::Resolve(res_vec) => {
-                            let new_mag = res_vec.magnitude();
-                            if *mag < new_mag {
-                                result = Some((new_mag, res))
-                            }
-                        }
+            match res {
+                GenericCollisionVector::Touch(_) => {
+                    if touch.is_none() {
+                        touch = Some(res);
+                    }
+                }
+                GenericCollisionVector::Resolve(res_vec) => {
+                    let mag = magnitude(&res_vec);
+                    match &deepest {
+                        Some((old_mag, _)) if *old_mag >= mag => {}
+                        _ => deepest = Some((mag, res)),
                     }
                 }
             }
         }
-        Some(result?.1)
+        match (deepest, touch) {
+            (Some((mag, res)), Some(touch)) => Some(if mag > tolerance { res } else { touch }),
+            (Some((_, res)), None) => Some(res),
+            (None, touch) => touch,
+        }
     }
-    pub fn receive_ray(&self, ray: Line, normal: InEq) -> Option<Vector2<f32>> {
+    pub fn receive_ray(&self, ray: GenericLine<T>, normal: GenericInEq<T>) -> Option<Vector2<T>> {
         self.iter_sides()
             .mangle()
             .filter_map(|(line, start, end)| line.intersection_segment(&ray, start, end))
             .filter(|&pt| normal.contains(pt))
             .fold(None, |prev, new_val| match prev {
                 Some(prev) => {
-                    if Vector2::new(new_val.x, new_val.y).magnitude() < Vector2::new(prev.x, prev.y).magnitude() {
+                    if magnitude(&new_val.coords) < magnitude(&prev) {
                         Some(Vector2::new(new_val.x, new_val.y))
                     } else {
                         Some(prev)
@@ -175,15 +227,29 @@ impl Shape {
                 None => Some(Vector2::new(new_val.x, new_val.y)),
             })
     }
-    pub fn set_transformation(&mut self, transform: &Transform) {
-        let mat = transform.matrix();
+    /// Smallest `t` for which `start + direction * t` lies on the outline of the shape.
+    pub fn cast_ray(&self, start: Point2<T>, direction: Vector2<T>) -> Option<T> {
+        self.iter_edges()
+            .filter_map(|(a, b)| ray_segment(start, direction, a, b))
+            .fold(None, |prev, t| match prev {
+                Some(prev) if prev <= t => Some(prev),
+                _ => Some(t),
+            })
+    }
+    pub fn contains(&self, point: Point2<T>) -> bool {
+        !self.moved_points.is_empty() && self.iter_sides().all(|side| side.contains(point))
+    }
+    pub fn set_pose(&mut self, pose: &GenericPose<T>) {
+        self.transform(|pt| pose.transform_point(pt));
+    }
+    pub fn set_matrix(&mut self, mat: &Matrix4<T>) {
         self.transform(|pt| {
-            let tpoint = Point4::new(pt.x, pt.y, 1., 1.);
+            let tpoint = Point4::new(pt.x, pt.y, T::one(), T::one());
             let tformed = mat * tpoint;
             Point2::new(tformed.x, tformed.y)
         });
     }
-    pub fn transform<F: FnMut(&Point2<f32>) -> Point2<f32>>(&mut self, mut func: F) {
+    pub fn transform<F: FnMut(&Point2<T>) -> Point2<T>>(&mut self, mut func: F) {
         for (point, p_out) in self.points.iter().map(&mut func).zip(self.moved_points.iter_mut()) {
             *p_out = point;
         }
@@ -191,12 +257,24 @@ impl Shape {
     }
 }
 
-impl PartialEq for Shape {
+fn average<T: Real>(points: &[Point2<T>]) -> Point2<T> {
+    let mut avg = Vector2::zeros();
+    for point in points {
+        avg += point.coords;
+    }
+    if !points.is_empty() {
+        avg /= T::from_f64(points.len() as f64);
+    }
+    Point2::from(avg)
+}
+
+impl<T: Real> PartialEq for GenericShape<T> {
     fn eq(&self, right: &Self) -> bool {
         self.moved_points == right.moved_points
     }
 }
 
-impl Component for Shape {
-    type Storage = DenseVecStorage<Self>;
-}
\ No newline at end of file
+#[cfg(feature = "specs")]
+impl<T: Real> Component for GenericShape<T> {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
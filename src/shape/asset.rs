@@ -0,0 +1,29 @@
+use super::{Shape, ShapePrefab};
+use amethyst::{
+    assets::{Asset, Handle, ProcessableAsset, ProcessingState},
+    ecs::{storage::{DenseVecStorage, FlaggedStorage}},
+    Error
+};
+
+/// Collider loaded on its own from a `.shape.ron` file, written like a `ShapePrefab`:
+/// `loader.load("hitbox.shape.ron", RonFormat, (), &storage)`.
+///
+/// Only the geometry of the prefab is used. Entities with a `Handle<ShapeAsset>` get their
+/// `Shape` from `ShapeSync`, which also picks up hot reloads.
+#[derive(Debug, Clone)]
+pub struct ShapeAsset(pub Shape);
+impl ShapeAsset {
+    pub fn shape(&self) -> &Shape {
+        &self.0
+    }
+}
+impl Asset for ShapeAsset {
+    const NAME: &'static str = "shapekit::ShapeAsset";
+    type Data = ShapePrefab;
+    type HandleStorage = FlaggedStorage<Handle<Self>, DenseVecStorage<Handle<Self>>>;
+}
+impl ProcessableAsset for ShapeAsset {
+    fn process(data: ShapePrefab) -> Result<ProcessingState<ShapeAsset>, Error> {
+        Ok(ProcessingState::Loaded(ShapeAsset(data.build()?)))
+    }
+}
@@ -0,0 +1,79 @@
+use nalgebra::{Point2, Vector2};
+use std::borrow::Borrow;
+use crate::scalar::Real;
+
+/// Axis aligned bounding box, used by the broad phase.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GenericAabb<T: Real> {
+    pub min: Point2<T>,
+    pub max: Point2<T>,
+}
+pub type Aabb = GenericAabb<f32>;
+
+impl<T: Real> GenericAabb<T> {
+    pub fn new(min: Point2<T>, max: Point2<T>) -> GenericAabb<T> {
+        GenericAabb { min, max }
+    }
+    pub fn from_points<P: Borrow<Point2<T>>, I: IntoIterator<Item = P>>(points: I) -> Option<GenericAabb<T>> {
+        let mut iter = points.into_iter();
+        let first = *iter.next()?.borrow();
+        Some(iter.fold(GenericAabb::new(first, first), |aabb, point| {
+            let point = point.borrow();
+            GenericAabb {
+                min: Point2::new(aabb.min.x.min(point.x), aabb.min.y.min(point.y)),
+                max: Point2::new(aabb.max.x.max(point.x), aabb.max.y.max(point.y)),
+            }
+        }))
+    }
+    pub fn merged(&self, other: &GenericAabb<T>) -> GenericAabb<T> {
+        GenericAabb {
+            min: Point2::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y)),
+            max: Point2::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y)),
+        }
+    }
+    pub fn translated(&self, by: Vector2<T>) -> GenericAabb<T> {
+        GenericAabb {
+            min: self.min + by,
+            max: self.max + by,
+        }
+    }
+    /// Grown by `margin` on every side.
+    pub fn expanded(&self, margin: T) -> GenericAabb<T> {
+        let margin = Vector2::new(margin, margin);
+        GenericAabb {
+            min: self.min - margin,
+            max: self.max + margin,
+        }
+    }
+    pub fn intersects(&self, other: &GenericAabb<T>) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+    pub fn contains(&self, point: Point2<T>) -> bool {
+        self.min.x <= point.x && point.x <= self.max.x && self.min.y <= point.y && point.y <= self.max.y
+    }
+    /// Returns the range of `t` for which `start + direction * t` (with `t >= 0`) is inside the box.
+    pub fn ray_interval(&self, start: Point2<T>, direction: Vector2<T>) -> Option<(T, T)> {
+        let mut t_min = T::zero();
+        let mut t_max = T::max_value();
+        for axis in 0..2 {
+            if direction[axis] == T::zero() {
+                if start[axis] < self.min[axis] || start[axis] > self.max[axis] {
+                    return None;
+                }
+            } else {
+                let a = (self.min[axis] - start[axis]) / direction[axis];
+                let b = (self.max[axis] - start[axis]) / direction[axis];
+                t_min = t_min.max(a.min(b));
+                t_max = t_max.min(a.max(b));
+                if t_min > t_max {
+                    return None;
+                }
+            }
+        }
+        Some((t_min, t_max))
+    }
+}
@@ -0,0 +1,100 @@
+use nalgebra::Point2;
+use crate::scalar::{cross, Real};
+use super::validate::signed_area;
+
+/// Splits a simple polygon into convex pieces: ear clipping into triangles, then
+/// Hertel-Mehlhorn to merge neighbouring triangles back together while they stay convex.
+pub(crate) fn decompose<T: Real>(points: &[Point2<T>]) -> Vec<Vec<Point2<T>>> {
+    let mut outline: Vec<Point2<T>> = Vec::with_capacity(points.len());
+    for point in points {
+        if outline.last() != Some(point) {
+            outline.push(*point);
+        }
+    }
+    while outline.len() > 1 && outline.first() == outline.last() {
+        outline.pop();
+    }
+    if signed_area(&outline) < T::zero() {
+        outline.reverse();
+    }
+    let turn = |a: usize, b: usize, c: usize| cross(&(outline[b] - outline[a]), &(outline[c] - outline[b]));
+    let mut pieces = triangulate(&outline, &turn);
+    'merge: loop {
+        for i in 0..pieces.len() {
+            for j in i + 1..pieces.len() {
+                if let Some(merged) = merge(&pieces[i], &pieces[j]) {
+                    if is_convex(&merged, &turn) {
+                        pieces[i] = merged;
+                        pieces.swap_remove(j);
+                        continue 'merge;
+                    }
+                }
+            }
+        }
+        break;
+    }
+    pieces
+        .into_iter()
+        .map(|piece| {
+            (0..piece.len())
+                .filter(|&i| turn(piece[(i + piece.len() - 1) % piece.len()], piece[i], piece[(i + 1) % piece.len()]) != T::zero())
+                .map(|i| outline[piece[i]])
+                .collect()
+        })
+        .collect()
+}
+
+fn triangulate<T: Real, F: Fn(usize, usize, usize) -> T>(outline: &[Point2<T>], turn: &F) -> Vec<Vec<usize>> {
+    let mut remaining: Vec<usize> = (0..outline.len()).collect();
+    let mut triangles = Vec::new();
+    let mut misses = 0;
+    let mut i = 0;
+    // Self-intersecting outlines run out of ears, which ends the loop after one fruitless lap.
+    while remaining.len() >= 3 && misses < remaining.len() {
+        let len = remaining.len();
+        let (a, b, c) = (remaining[(i + len - 1) % len], remaining[i % len], remaining[(i + 1) % len]);
+        let bend = turn(a, b, c);
+        if bend == T::zero() {
+            remaining.remove(i % len);
+            misses = 0;
+            continue;
+        }
+        let ear = bend > T::zero()
+            && remaining
+                .iter()
+                .filter(|&&other| other != a && other != b && other != c && outline[other] != outline[a] && outline[other] != outline[b] && outline[other] != outline[c])
+                .all(|&other| !in_triangle(outline[other], outline[a], outline[b], outline[c]));
+        if ear {
+            triangles.push(vec![a, b, c]);
+            remaining.remove(i % len);
+            misses = 0;
+        } else {
+            i = (i + 1) % len;
+            misses += 1;
+        }
+    }
+    triangles
+}
+
+fn in_triangle<T: Real>(point: Point2<T>, a: Point2<T>, b: Point2<T>, c: Point2<T>) -> bool {
+    cross(&(b - a), &(point - a)) >= T::zero()
+        && cross(&(c - b), &(point - b)) >= T::zero()
+        && cross(&(a - c), &(point - c)) >= T::zero()
+}
+
+/// Joins two pieces along an edge they share, or returns `None` if they have none.
+fn merge(first: &[usize], second: &[usize]) -> Option<Vec<usize>> {
+    for i in 0..first.len() {
+        let (a, b) = (first[i], first[(i + 1) % first.len()]);
+        if let Some(j) = (0..second.len()).find(|&j| second[j] == b && second[(j + 1) % second.len()] == a) {
+            let mut merged: Vec<usize> = (0..first.len()).map(|k| first[(i + 1 + k) % first.len()]).collect();
+            merged.extend((2..second.len()).map(|k| second[(j + k) % second.len()]));
+            return Some(merged);
+        }
+    }
+    None
+}
+
+fn is_convex<T: Real, F: Fn(usize, usize, usize) -> T>(piece: &[usize], turn: &F) -> bool {
+    (0..piece.len()).all(|i| turn(piece[(i + piece.len() - 1) % piece.len()], piece[i], piece[(i + 1) % piece.len()]) >= T::zero())
+}
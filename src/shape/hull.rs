@@ -0,0 +1,38 @@
+use nalgebra::Point2;
+use std::cmp::Ordering;
+use crate::scalar::{cross, Real};
+
+/// Andrew's monotone chain. Returns the hull counter-clockwise, without collinear points.
+pub(crate) fn convex_hull<T: Real>(mut points: Vec<Point2<T>>) -> Vec<Point2<T>> {
+    points.sort_by(|a, b| {
+        a.x.partial_cmp(&b.x)
+            .unwrap_or(Ordering::Equal)
+            .then(a.y.partial_cmp(&b.y).unwrap_or(Ordering::Equal))
+    });
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    let turns_left = |hull: &[Point2<T>], point: &Point2<T>| {
+        let (a, b) = (hull[hull.len() - 2], hull[hull.len() - 1]);
+        cross(&(b - a), &(point - b)) > T::zero()
+    };
+    let mut lower: Vec<Point2<T>> = Vec::with_capacity(points.len());
+    for point in &points {
+        while lower.len() >= 2 && !turns_left(&lower, point) {
+            lower.pop();
+        }
+        lower.push(*point);
+    }
+    let mut upper: Vec<Point2<T>> = Vec::with_capacity(points.len());
+    for point in points.iter().rev() {
+        while upper.len() >= 2 && !turns_left(&upper, point) {
+            upper.pop();
+        }
+        upper.push(*point);
+    }
+    lower.pop();
+    upper.pop();
+    lower.extend(upper);
+    lower
+}
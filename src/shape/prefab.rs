@@ -0,0 +1,195 @@
+use super::{Shape, ShapeError};
+use crate::pose::Pose;
+use crate::processing::world::{Layers, Sensor, ALL_LAYERS};
+use amethyst::{
+    core::{
+        math::{Point2, Vector2},
+        transform::Transform
+    },
+    assets::{PrefabData},
+    ecs::{
+        Entity,
+        WriteStorage
+    },
+    Error
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Collider geometry, written in RON as for example `Rect(width: 32, height: 16)`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ShapeKind {
+    Polygon { points: Vec<Point2<f32>> },
+    /// Convex hull of a point cloud.
+    Hull { points: Vec<Point2<f32>> },
+    Rect { width: f32, height: f32 },
+    RectCentered { width: f32, height: f32 },
+    RegularPolygon { sides: usize, radius: f32 },
+    Circle { radius: f32, segments: usize },
+    Ellipse { radius_x: f32, radius_y: f32, segments: usize },
+    RoundedRect { width: f32, height: f32, radius: f32, segments: usize },
+}
+impl ShapeKind {
+    /// Builds the shape. Unlike the `Shape` constructors, which quietly raise too few sides or segments,
+    /// sizes that aren't positive and fewer than 3 sides or segments are errors here.
+    pub fn build(&self) -> Result<Shape, ShapeError> {
+        let shape = match *self {
+            ShapeKind::Polygon { ref points } => return Shape::try_new(points),
+            ShapeKind::Hull { ref points } => return Shape::convex_hull(points),
+            ShapeKind::Rect { width, height } => {
+                positive(&[width, height])?;
+                Shape::rect(width, height)
+            }
+            ShapeKind::RectCentered { width, height } => {
+                positive(&[width, height])?;
+                Shape::rect_centered(width, height)
+            }
+            ShapeKind::RegularPolygon { sides, radius } => {
+                positive(&[radius])?;
+                enough(sides, 3)?;
+                Shape::regular_polygon(sides, radius)
+            }
+            ShapeKind::Circle { radius, segments } => {
+                positive(&[radius])?;
+                enough(segments, 3)?;
+                Shape::ellipse(radius, radius, segments)
+            }
+            ShapeKind::Ellipse { radius_x, radius_y, segments } => {
+                positive(&[radius_x, radius_y])?;
+                enough(segments, 3)?;
+                Shape::ellipse(radius_x, radius_y, segments)
+            }
+            ShapeKind::RoundedRect { width, height, radius, segments } => {
+                positive(&[width, height])?;
+                if radius.is_nan() || radius < 0. {
+                    return Err(ShapeError::InvalidSize);
+                }
+                enough(segments, 1)?;
+                Shape::rounded_rect(width, height, radius, segments)
+            }
+        };
+        Shape::try_new(shape.iter_points())
+    }
+}
+
+fn positive(sizes: &[f32]) -> Result<(), ShapeError> {
+    if sizes.iter().all(|&size| size > 0.) {
+        Ok(())
+    } else {
+        Err(ShapeError::InvalidSize)
+    }
+}
+
+fn enough(count: usize, minimum: usize) -> Result<(), ShapeError> {
+    if count >= minimum {
+        Ok(())
+    } else {
+        Err(ShapeError::TooFewPoints(count))
+    }
+}
+
+/// A collider and its settings, for example
+/// `(shape: Circle(radius: 8, segments: 12), offset: [0, 4], layers: 2, sensor: true)`.
+///
+/// Old prefabs that only list `points` still load as a polygon.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShapePrefab {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub points: Vec<Point2<f32>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_kind",
+        deserialize_with = "deserialize_kind"
+    )]
+    pub shape: Option<ShapeKind>,
+    /// Moves the collider away from the origin of the entity.
+    #[serde(default = "Vector2::zeros")]
+    pub offset: Vector2<f32>,
+    /// Rotation around the origin of the entity, in radians, applied before `offset`.
+    #[serde(default)]
+    pub rotation: f32,
+    /// Added as a `Layers` component unless it is every layer.
+    #[serde(default = "all_layers")]
+    pub layers: u32,
+    /// Adds a `Sensor` component.
+    #[serde(default)]
+    pub sensor: bool,
+}
+impl ShapePrefab {
+    /// Builds the shape, preferring `shape` over `points` when both are set.
+    pub fn build(&self) -> Result<Shape, ShapeError> {
+        let shape = match &self.shape {
+            Some(kind) => kind.build()?,
+            None => Shape::try_new(&self.points)?,
+        };
+        if self.offset == Vector2::zeros() && self.rotation == 0. {
+            return Ok(shape);
+        }
+        let local = Pose::new(self.offset, self.rotation, Vector2::new(1., 1.));
+        Ok(Shape::new(shape.iter_points().map(|point| local.transform_point(point))))
+    }
+}
+impl Default for ShapePrefab {
+    fn default() -> Self {
+        ShapePrefab {
+            points: Vec::new(),
+            shape: None,
+            offset: Vector2::zeros(),
+            rotation: 0.,
+            layers: ALL_LAYERS,
+            sensor: false,
+        }
+    }
+}
+impl<'a> PrefabData<'a> for ShapePrefab {
+    type SystemData = (
+        WriteStorage<'a, Shape>,
+        WriteStorage<'a, Layers>,
+        WriteStorage<'a, Sensor>,
+    );
+
+    type Result = ();
+
+    fn add_to_entity(
+        &self,
+        entity: Entity,
+        (shapes, layers, sensors): &mut Self::SystemData,
+        _entities: &[Entity],
+        _children: &[Entity],
+    ) -> Result<(), Error> {
+        shapes.insert(entity, self.build()?)?;
+        if self.layers != ALL_LAYERS {
+            layers.insert(entity, Layers(self.layers))?;
+        }
+        if self.sensor {
+            sensors.insert(entity, Sensor)?;
+        }
+        Ok(())
+    }
+}
+
+fn all_layers() -> u32 {
+    ALL_LAYERS
+}
+
+// `shape` is written without `Some(...)`, which RON would otherwise require.
+fn serialize_kind<S: Serializer>(kind: &Option<ShapeKind>, serializer: S) -> Result<S::Ok, S::Error> {
+    match kind {
+        Some(kind) => kind.serialize(serializer),
+        None => serializer.serialize_none(),
+    }
+}
+fn deserialize_kind<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<ShapeKind>, D::Error> {
+    ShapeKind::deserialize(deserializer).map(Some)
+}
+
+impl Shape {
+    /// Moves the shape by the 2D part of the local matrix of `transform`, ignoring its parents.
+    pub fn set_transformation(&mut self, transform: &Transform) {
+        self.set_pose(&Pose::from_matrix(&transform.matrix()));
+    }
+    /// Moves the shape into world space, using the global matrix computed by amethyst's `TransformSystem`.
+    pub fn set_global_transformation(&mut self, transform: &Transform) {
+        self.set_pose(&Pose::from_matrix(transform.global_matrix()));
+    }
+}
@@ -0,0 +1,72 @@
+use nalgebra::Point2;
+use crate::scalar::Real;
+use super::GenericShape;
+
+impl<T: Real> GenericShape<T> {
+    /// Rectangle with one corner at the origin and the opposite one at `(width, height)`.
+    pub fn rect(width: T, height: T) -> GenericShape<T> {
+        GenericShape::new([
+            Point2::new(T::zero(), T::zero()),
+            Point2::new(width, T::zero()),
+            Point2::new(width, height),
+            Point2::new(T::zero(), height),
+        ])
+    }
+    /// Rectangle centered on the origin.
+    pub fn rect_centered(width: T, height: T) -> GenericShape<T> {
+        let (x, y) = (half(width), half(height));
+        GenericShape::new([Point2::new(-x, -y), Point2::new(x, -y), Point2::new(x, y), Point2::new(-x, y)])
+    }
+    /// Polygon with `sides` equal sides (at least 3) and its corners `radius` away from the origin.
+    pub fn regular_polygon(sides: usize, radius: T) -> GenericShape<T> {
+        GenericShape::ellipse(radius, radius, sides)
+    }
+    /// Ellipse centered on the origin, approximated by `segments` (at least 3) straight edges.
+    pub fn ellipse(radius_x: T, radius_y: T, segments: usize) -> GenericShape<T> {
+        let segments = segments.max(3);
+        GenericShape::new((0..segments).map(|i| {
+            let (sin, cos) = turn::<T>(i, segments).sin_cos();
+            Point2::new(radius_x * cos, radius_y * sin)
+        }))
+    }
+    /// Rectangle centered on the origin whose corners are quarter circles made of `segments` edges.
+    pub fn rounded_rect(width: T, height: T, radius: T, segments: usize) -> GenericShape<T> {
+        let (x, y) = (half(width), half(height));
+        let radius = radius.min(x).min(y);
+        if radius <= T::zero() {
+            return GenericShape::rect_centered(width, height);
+        }
+        let segments = segments.max(1);
+        let corners = [(x, y), (-x, y), (-x, -y), (x, -y)];
+        let mut points: Vec<Point2<T>> = Vec::with_capacity(4 * (segments + 1));
+        for (corner, &(cx, cy)) in corners.iter().enumerate() {
+            let center = Point2::new(cx - radius * sign(cx), cy - radius * sign(cy));
+            for step in 0..=segments {
+                let (sin, cos) = turn::<T>(corner * segments + step, 4 * segments).sin_cos();
+                let point = Point2::new(center.x + radius * cos, center.y + radius * sin);
+                // Arcs meet in one point when the radius is half the width or height.
+                if points.last() != Some(&point) && points.first() != Some(&point) {
+                    points.push(point);
+                }
+            }
+        }
+        GenericShape::new(points)
+    }
+}
+
+fn half<T: Real>(value: T) -> T {
+    value / (T::one() + T::one())
+}
+
+/// Angle of `step` out of `steps` in a full turn.
+fn turn<T: Real>(step: usize, steps: usize) -> T {
+    (T::pi() + T::pi()) * T::from_f64(step as f64) / T::from_f64(steps as f64)
+}
+
+fn sign<T: Real>(value: T) -> T {
+    if value < T::zero() {
+        -T::one()
+    } else {
+        T::one()
+    }
+}
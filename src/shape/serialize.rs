@@ -0,0 +1,27 @@
+use nalgebra::Point2;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use crate::scalar::Real;
+use super::GenericShape;
+
+// Only the authored outline is saved. The moved points and the center are rebuilt on load.
+#[derive(Serialize)]
+#[serde(rename = "Shape")]
+struct Authored<'a, T: Real> {
+    points: &'a [Point2<T>],
+}
+#[derive(Deserialize)]
+#[serde(rename = "Shape")]
+struct Owned<T: Real> {
+    points: Vec<Point2<T>>,
+}
+
+impl<T: Real + Serialize> Serialize for GenericShape<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Authored { points: &self.points }.serialize(serializer)
+    }
+}
+impl<'de, T: Real + Deserialize<'de>> Deserialize<'de> for GenericShape<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<GenericShape<T>, D::Error> {
+        Owned::deserialize(deserializer).map(|shape| GenericShape::new(shape.points))
+    }
+}
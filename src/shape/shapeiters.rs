@@ -1,25 +1,26 @@
-use crate::lines::{InEq, Line};
+use crate::lines::{GenericInEq, GenericLine};
 use std::iter::Peekable;
 use std::slice;
-use amethyst::core::math::Point2;
+use nalgebra::Point2;
+use crate::scalar::Real;
 
-pub struct SidesIter<'a> {
-    pub points: Peekable<slice::Iter<'a, Point2<f32>>>,
-    pub center: Point2<f32>,
-    pub first: Point2<f32>
+pub struct SidesIter<'a, T: Real> {
+    pub points: Peekable<slice::Iter<'a, Point2<T>>>,
+    pub center: Point2<T>,
+    pub first: Point2<T>
 }
-impl<'a> Iterator for SidesIter<'a> {
-    type Item = InEq;
-    fn next(&mut self) -> Option<InEq> {
+impl<'a, T: Real> Iterator for SidesIter<'a, T> {
+    type Item = GenericInEq<T>;
+    fn next(&mut self) -> Option<GenericInEq<T>> {
         let a = *self.points.next()?;
         match self.points.peek() {
-            Some(&&b) => Some(Line::through(a, b).initialize(self.center)),
-            None => Some(Line::through(a, self.first).initialize(self.center)),
+            Some(&&b) => Some(GenericLine::through(a, b).initialize(self.center)),
+            None => Some(GenericLine::through(a, self.first).initialize(self.center)),
         }
     }
 }
-impl<'a> SidesIter<'a> {
-    pub fn mangle(self) -> Mangled<'a> {
+impl<'a, T: Real> SidesIter<'a, T> {
+    pub fn mangle(self) -> Mangled<'a, T> {
         Mangled {
             points: self.points,
             first: self.first,
@@ -27,18 +28,18 @@ impl<'a> SidesIter<'a> {
     }
 }
 
-pub struct Mangled<'a> {
-    pub points: Peekable<slice::Iter<'a, Point2<f32>>>,
-    pub first: Point2<f32>
+pub struct Mangled<'a, T: Real> {
+    pub points: Peekable<slice::Iter<'a, Point2<T>>>,
+    pub first: Point2<T>
 }
-impl<'a> Iterator for Mangled<'a> {
-    type Item = (Line, Point2<f32>, Point2<f32>);
+impl<'a, T: Real> Iterator for Mangled<'a, T> {
+    type Item = (GenericLine<T>, Point2<T>, Point2<T>);
 
     fn next(&mut self) -> Option<Self::Item> {
         let a = *self.points.next()?;
         match self.points.peek() {
-            Some(&&b) => Some((Line::through(a, b), a, b)),
-            None => Some((Line::through(a, self.first), a, self.first)),
+            Some(&&b) => Some((GenericLine::through(a, b), a, b)),
+            None => Some((GenericLine::through(a, self.first), a, self.first)),
         }
     }
 }
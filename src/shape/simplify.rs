@@ -0,0 +1,123 @@
+use nalgebra::{Point2, Vector2};
+use std::cmp::Ordering;
+use crate::scalar::{cross, Real};
+
+/// Ramer-Douglas-Peucker simplification of a closed outline: drops every point closer than
+/// `tolerance` to the line between the points kept around it.
+///
+/// The result is a subset of the outline, so a convex outline stays convex. At least three points are kept.
+pub fn ramer_douglas_peucker<T: Real>(points: &[Point2<T>], tolerance: T) -> Vec<Point2<T>> {
+    filter(points, &ramer_douglas_peucker_mask(points, tolerance))
+}
+
+/// Visvalingam-Whyatt simplification of a closed outline: repeatedly drops the point whose triangle
+/// with its two neighbours has the smallest area, until every such triangle is at least `area`.
+///
+/// The result is a subset of the outline, so a convex outline stays convex. At least three points are kept.
+pub fn visvalingam_whyatt<T: Real>(points: &[Point2<T>], area: T) -> Vec<Point2<T>> {
+    filter(points, &visvalingam_whyatt_mask(points, area))
+}
+
+pub(crate) fn filter<T: Copy>(items: &[T], keep: &[bool]) -> Vec<T> {
+    items.iter().zip(keep).filter(|&(_, &keep)| keep).map(|(item, _)| *item).collect()
+}
+
+pub(crate) fn ramer_douglas_peucker_mask<T: Real>(points: &[Point2<T>], tolerance: T) -> Vec<bool> {
+    if points.len() <= 3 {
+        return vec![true; points.len()];
+    }
+    // Split the loop at the point farthest from the first, then simplify both halves as open chains.
+    let far = farthest(1..points.len(), |i| squared(points[i] - points[0]));
+    let mut closed: Vec<Point2<T>> = points.to_vec();
+    closed.push(points[0]);
+    let mut keep = vec![false; closed.len()];
+    keep[0] = true;
+    keep[far] = true;
+    mark(&closed, 0, far, tolerance * tolerance, &mut keep);
+    mark(&closed, far, points.len(), tolerance * tolerance, &mut keep);
+    keep.truncate(points.len());
+    // The first point was kept only to split the loop, so it gets the same test as the others.
+    let kept: Vec<usize> = (0..points.len()).filter(|&i| keep[i]).collect();
+    if kept.len() > 3 && distance_squared(points[0], points[kept[kept.len() - 1]], points[kept[1]]) <= tolerance * tolerance {
+        keep[0] = false;
+    }
+    if keep.iter().filter(|&&keep| keep).count() < 3 {
+        // Everything lies within the tolerance of one line. Keep the widest triangle instead.
+        let third = farthest((1..points.len()).filter(|&i| i != far), |i| distance_squared(points[i], points[0], points[far]));
+        keep[third] = true;
+    }
+    keep
+}
+
+pub(crate) fn visvalingam_whyatt_mask<T: Real>(points: &[Point2<T>], area: T) -> Vec<bool> {
+    let mut keep = vec![true; points.len()];
+    let mut remaining = points.len();
+    let two = T::one() + T::one();
+    let neighbours = |keep: &[bool], i: usize| {
+        let previous = (1..points.len()).map(|step| (i + points.len() - step) % points.len()).find(|&j| keep[j]).unwrap_or(i);
+        let next = (1..points.len()).map(|step| (i + step) % points.len()).find(|&j| keep[j]).unwrap_or(i);
+        (previous, next)
+    };
+    let triangle = |keep: &[bool], i: usize| {
+        let (previous, next) = neighbours(keep, i);
+        cross(&(points[i] - points[previous]), &(points[next] - points[i])).abs() / two
+    };
+    let mut areas: Vec<T> = (0..points.len()).map(|i| triangle(&keep, i)).collect();
+    while remaining > 3 {
+        let smallest = (0..points.len())
+            .filter(|&i| keep[i])
+            .min_by(|&a, &b| areas[a].partial_cmp(&areas[b]).unwrap_or(Ordering::Equal));
+        let smallest = match smallest {
+            Some(i) if areas[i] < area => i,
+            _ => break,
+        };
+        keep[smallest] = false;
+        remaining -= 1;
+        let (previous, next) = neighbours(&keep, smallest);
+        areas[previous] = triangle(&keep, previous);
+        areas[next] = triangle(&keep, next);
+    }
+    keep
+}
+
+fn mark<T: Real>(points: &[Point2<T>], start: usize, end: usize, tolerance_squared: T, keep: &mut [bool]) {
+    let mut stack = vec![(start, end)];
+    while let Some((start, end)) = stack.pop() {
+        if end <= start + 1 {
+            continue;
+        }
+        let (a, b) = (points[start], points[end]);
+        let index = farthest(start + 1..end, |i| distance_squared(points[i], a, b));
+        if distance_squared(points[index], a, b) > tolerance_squared {
+            keep[index] = true;
+            stack.push((start, index));
+            stack.push((index, end));
+        }
+    }
+}
+
+/// The index with the largest `distance`, the first one on ties.
+fn farthest<T: Real, I: Iterator<Item = usize>, F: Fn(usize) -> T>(indices: I, distance: F) -> usize {
+    indices
+        .map(|i| (i, distance(i)))
+        .fold(None, |best: Option<(usize, T)>, next| match best {
+            Some(best) if best.1 >= next.1 => Some(best),
+            _ => Some(next),
+        })
+        .map_or(0, |(i, _)| i)
+}
+
+/// Squared distance from `point` to the line through `a` and `b`.
+fn distance_squared<T: Real>(point: Point2<T>, a: Point2<T>, b: Point2<T>) -> T {
+    let line = b - a;
+    let length = squared(line);
+    if length == T::zero() {
+        return squared(point - a);
+    }
+    let area = cross(&line, &(point - a));
+    area * area / length
+}
+
+fn squared<T: Real>(vector: Vector2<T>) -> T {
+    vector.x * vector.x + vector.y * vector.y
+}
@@ -0,0 +1,114 @@
+use nalgebra::Point2;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use crate::scalar::{cross, Real};
+
+/// Why a point list can't be turned into a `Shape`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ShapeError {
+    /// A polygon needs at least three points, this is how many were given.
+    TooFewPoints(usize),
+    /// The point at this index appears earlier in the list.
+    DuplicatePoint(usize),
+    /// All points are on one line.
+    DegenerateArea,
+    /// Two edges that don't share a point cross each other.
+    SelfIntersection,
+    /// The outline turns both ways. Collisions only work with convex shapes.
+    NonConvex,
+    /// A width, height or radius of a primitive shape is zero or negative. Corner radii may be zero.
+    InvalidSize,
+}
+impl Display for ShapeError {
+    fn fmt(&self, form: &mut Formatter) -> fmt::Result {
+        match self {
+            ShapeError::TooFewPoints(count) => write!(form, "a shape needs at least 3 points, got {}", count),
+            ShapeError::DuplicatePoint(index) => write!(form, "point {} is a duplicate", index),
+            ShapeError::DegenerateArea => write!(form, "the shape has no area"),
+            ShapeError::SelfIntersection => write!(form, "the outline of the shape intersects itself"),
+            ShapeError::NonConvex => write!(form, "the shape is not convex"),
+            ShapeError::InvalidSize => write!(form, "the sizes of a primitive shape must be positive"),
+        }
+    }
+}
+impl Error for ShapeError {}
+
+pub(crate) fn validate<T: Real>(points: &[Point2<T>]) -> Result<(), ShapeError> {
+    if points.len() < 3 {
+        return Err(ShapeError::TooFewPoints(points.len()));
+    }
+    for (index, point) in points.iter().enumerate() {
+        if points[..index].contains(point) {
+            return Err(ShapeError::DuplicatePoint(index));
+        }
+    }
+    let edge = |i: usize| (points[i], points[(i + 1) % points.len()]);
+    for i in 0..points.len() {
+        // Neighbouring edges share a point, so only edges two or more apart can cross.
+        for j in i + 2..points.len() {
+            if i == 0 && j == points.len() - 1 {
+                continue;
+            }
+            if segments_cross(edge(i), edge(j)) {
+                return Err(ShapeError::SelfIntersection);
+            }
+        }
+    }
+    // A figure eight has no net area either, so this only runs once crossings are ruled out.
+    if signed_area(points) == T::zero() {
+        return Err(ShapeError::DegenerateArea);
+    }
+    let turns = (0..points.len()).map(|i| {
+        let (a, b) = edge(i);
+        let (_, c) = edge((i + 1) % points.len());
+        cross(&(b - a), &(c - b))
+    });
+    let (mut left, mut right) = (false, false);
+    for turn in turns {
+        left |= turn > T::zero();
+        right |= turn < T::zero();
+    }
+    if left && right {
+        return Err(ShapeError::NonConvex);
+    }
+    Ok(())
+}
+
+/// Twice the signed area, positive for counter-clockwise outlines.
+/// Measured from the first point, so the products stay small for shapes far from the origin.
+pub(crate) fn signed_area<T: Real>(points: &[Point2<T>]) -> T {
+    let origin = match points.first() {
+        Some(&origin) => origin,
+        None => return T::zero(),
+    };
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .fold(T::zero(), |area, (a, b)| area + cross(&(a - origin), &(b - origin)))
+}
+
+fn segments_cross<T: Real>((a, b): (Point2<T>, Point2<T>), (c, d): (Point2<T>, Point2<T>)) -> bool {
+    let side = |p: Point2<T>, q: Point2<T>, r: Point2<T>| {
+        let turn = cross(&(q - p), &(r - p));
+        if turn > T::zero() {
+            1
+        } else if turn < T::zero() {
+            -1
+        } else {
+            0
+        }
+    };
+    let on_segment = |p: Point2<T>, q: Point2<T>, r: Point2<T>| {
+        r.x >= p.x.min(q.x) && r.x <= p.x.max(q.x) && r.y >= p.y.min(q.y) && r.y <= p.y.max(q.y)
+    };
+    let (d1, d2) = (side(a, b, c), side(a, b, d));
+    let (d3, d4) = (side(c, d, a), side(c, d, b));
+    if d1 * d2 < 0 && d3 * d4 < 0 {
+        return true;
+    }
+    (d1 == 0 && on_segment(a, b, c))
+        || (d2 == 0 && on_segment(a, b, d))
+        || (d3 == 0 && on_segment(c, d, a))
+        || (d4 == 0 && on_segment(c, d, b))
+}
@@ -0,0 +1,197 @@
+//! SVG pictures of shapes and collision state, for tests and bug reports, and with the `svg_import`
+//! feature, shapes read from SVG drawings.
+#[cfg(feature = "svg_import")]
+mod import;
+#[cfg(feature = "svg_import")]
+mod path;
+#[cfg(feature = "svg_import")]
+pub use import::{by_name, SvgError, SvgLoader, SvgShape};
+use crate::lines::GenericLine;
+use crate::scalar::Real;
+use crate::shape::{GenericAabb, GenericCollisionVector, GenericShape};
+use nalgebra::{Point2, Vector2};
+use std::fmt::{self, Display, Formatter, Write};
+
+#[derive(Debug, Clone)]
+enum Mark {
+    Polygon { points: Vec<Point2<f64>>, color: String },
+    Line { from: Point2<f64>, to: Point2<f64>, color: String, dashed: bool },
+    Arrow { from: Point2<f64>, to: Point2<f64>, color: String, dashed: bool },
+    Dot { at: Point2<f64>, color: String },
+}
+
+/// Collects shapes, rays and collision vectors, then renders them as an SVG document.
+///
+/// Colors are any SVG color, like `"red"` or `"#4a90d9"`. The view box is fitted around everything drawn,
+/// and `to_string` returns the document.
+#[derive(Debug, Clone)]
+pub struct DebugDrawing {
+    marks: Vec<Mark>,
+    /// Width in pixels of the larger side of the picture.
+    pub size: f64,
+    /// SVG points y down. On by default, since game worlds usually point y up.
+    pub flip_y: bool,
+}
+impl Default for DebugDrawing {
+    fn default() -> Self {
+        DebugDrawing {
+            marks: Vec::new(),
+            size: 800.,
+            flip_y: true,
+        }
+    }
+}
+impl DebugDrawing {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// The outline of a shape, lightly filled, with a dot on its centroid.
+    pub fn shape<T: Real>(&mut self, shape: &GenericShape<T>, color: &str) -> &mut Self {
+        let points = shape.iter_points().map(|&point| convert(point)).collect();
+        self.marks.push(Mark::Polygon { points, color: color.to_string() });
+        self.dot(shape.centroid(), color)
+    }
+    /// A dashed bounding box.
+    pub fn aabb<T: Real>(&mut self, aabb: &GenericAabb<T>, color: &str) -> &mut Self {
+        let (min, max) = (convert(aabb.min), convert(aabb.max));
+        let corners = [min, Point2::new(max.x, min.y), max, Point2::new(min.x, max.y)];
+        for i in 0..4 {
+            self.marks.push(Mark::Line { from: corners[i], to: corners[(i + 1) % 4], color: color.to_string(), dashed: true });
+        }
+        self
+    }
+    /// A dashed ray from `start` to `end`, for example the hit point of a raycast.
+    pub fn ray<T: Real>(&mut self, start: Point2<T>, end: Point2<T>, color: &str) -> &mut Self {
+        self.marks.push(Mark::Line { from: convert(start), to: convert(end), color: color.to_string(), dashed: true });
+        self.dot(start, color)
+    }
+    /// A marker, for example where a ray hit.
+    pub fn dot<T: Real>(&mut self, at: Point2<T>, color: &str) -> &mut Self {
+        self.marks.push(Mark::Dot { at: convert(at), color: color.to_string() });
+        self
+    }
+    /// One entry of `Collisions` as an arrow from the centroid of `shape`, dashed for a `Touch`.
+    pub fn collision<T: Real>(&mut self, shape: &GenericShape<T>, vector: &GenericCollisionVector<T>, color: &str) -> &mut Self {
+        let dashed = matches!(vector, GenericCollisionVector::Touch(_));
+        self.arrow(shape, **vector, color, dashed)
+    }
+    /// The summed `Collisions::resolution` as an arrow from the centroid of `shape`.
+    pub fn resolution<T: Real>(&mut self, shape: &GenericShape<T>, resolution: Vector2<T>, color: &str) -> &mut Self {
+        self.arrow(shape, resolution, color, false)
+    }
+    /// Marks where two shapes touch: the points where their edges cross, and the vertices of each shape
+    /// that lie inside the other.
+    pub fn contacts<T: Real>(&mut self, a: &GenericShape<T>, b: &GenericShape<T>, color: &str) -> &mut Self {
+        let mut points: Vec<Point2<T>> = a
+            .iter_points()
+            .filter(|&&point| b.contains(point))
+            .chain(b.iter_points().filter(|&&point| a.contains(point)))
+            .cloned()
+            .collect();
+        for (a_start, a_end) in edges(a) {
+            let a_line = GenericLine::through(a_start, a_end);
+            for (b_start, b_end) in edges(b) {
+                let b_line = GenericLine::through(b_start, b_end);
+                if b_line.intersection_segment(&a_line, b_start, b_end).is_some() {
+                    points.extend(a_line.intersection_segment(&b_line, a_start, a_end));
+                }
+            }
+        }
+        for point in points {
+            self.dot(point, color);
+        }
+        self
+    }
+
+    fn arrow<T: Real>(&mut self, shape: &GenericShape<T>, vector: Vector2<T>, color: &str, dashed: bool) -> &mut Self {
+        let from = convert(shape.centroid());
+        let to = from + Vector2::new(vector.x.to_f64(), vector.y.to_f64());
+        self.marks.push(Mark::Arrow { from, to, color: color.to_string(), dashed });
+        self
+    }
+    fn bounds(&self) -> Option<GenericAabb<f64>> {
+        GenericAabb::from_points(self.marks.iter().flat_map(|mark| match mark {
+            Mark::Polygon { points, .. } => points.clone(),
+            Mark::Line { from, to, .. } | Mark::Arrow { from, to, .. } => vec![*from, *to],
+            Mark::Dot { at, .. } => vec![*at],
+        }))
+    }
+}
+
+impl Display for DebugDrawing {
+    fn fmt(&self, form: &mut Formatter) -> fmt::Result {
+        let sign = if self.flip_y { -1. } else { 1. };
+        let bounds = self.bounds().unwrap_or_else(|| GenericAabb::new(Point2::origin(), Point2::new(1., 1.)));
+        let extent = bounds.max - bounds.min;
+        let margin = extent.x.max(extent.y).max(1.) / 20.;
+        let (width, height) = (extent.x + 2. * margin, extent.y + 2. * margin);
+        let top = if self.flip_y { -bounds.max.y } else { bounds.min.y } - margin;
+        let scale = self.size / width.max(height);
+        // Dots and arrow heads scale with the picture, lines are kept one pixel wide.
+        let dot = margin / 4.;
+        let point = |point: &Point2<f64>| format!("{},{}", number(point.x), number(sign * point.y));
+        writeln!(
+            form,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="{}" height="{}">"#,
+            number(bounds.min.x - margin),
+            number(top),
+            number(width),
+            number(height),
+            number(width * scale),
+            number(height * scale)
+        )?;
+        for mark in &self.marks {
+            match mark {
+                Mark::Polygon { points, color } => {
+                    let points: Vec<String> = points.iter().map(point).collect();
+                    writeln!(
+                        form,
+                        r#"  <polygon points="{}" fill="{}" fill-opacity="0.25" stroke="{}" vector-effect="non-scaling-stroke"/>"#,
+                        points.join(" "),
+                        color,
+                        color
+                    )?;
+                }
+                Mark::Line { from, to, color, dashed } | Mark::Arrow { from, to, color, dashed } => {
+                    let mut line = String::new();
+                    write!(line, r#"  <polyline points="{} {}" fill="none" stroke="{}" vector-effect="non-scaling-stroke""#, point(from), point(to), color)?;
+                    if *dashed {
+                        line.push_str(r#" stroke-dasharray="4 3""#);
+                    }
+                    writeln!(form, "{}/>", line)?;
+                    let direction = to - from;
+                    let length = (direction.x * direction.x + direction.y * direction.y).sqrt();
+                    if matches!(mark, Mark::Arrow { .. }) && length > 0. {
+                        let back = direction / length * (dot * 3.).min(length / 2.);
+                        let side = Vector2::new(-back.y, back.x) / 2.;
+                        let head = [*to, to - back + side, to - back - side];
+                        let head: Vec<String> = head.iter().map(point).collect();
+                        writeln!(form, r#"  <polygon points="{}" fill="{}"/>"#, head.join(" "), color)?;
+                    }
+                }
+                Mark::Dot { at, color } => {
+                    writeln!(form, r#"  <circle cx="{}" cy="{}" r="{}" fill="{}"/>"#, number(at.x), number(sign * at.y), number(dot), color)?;
+                }
+            }
+        }
+        write!(form, "</svg>")
+    }
+}
+
+fn edges<T: Real>(shape: &GenericShape<T>) -> impl Iterator<Item = (Point2<T>, Point2<T>)> + '_ {
+    shape.iter_points().zip(shape.iter_points().cycle().skip(1)).map(|(&a, &b)| (a, b))
+}
+
+fn convert<T: Real>(point: Point2<T>) -> Point2<f64> {
+    Point2::new(point.x.to_f64(), point.y.to_f64())
+}
+
+/// At most three decimals, without trailing zeros.
+fn number(value: f64) -> String {
+    let text = format!("{:.3}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" => "0".to_string(),
+        _ => text.to_string(),
+    }
+}
@@ -0,0 +1,266 @@
+use super::path;
+use crate::scalar::Real;
+use crate::shape::GenericShape;
+use nalgebra::{Matrix3, Point2, Vector2};
+use roxmltree::{Document, Node};
+use std::collections::HashMap;
+use std::error::Error;
+use std::f64::consts::{FRAC_PI_2, PI};
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Why an SVG file couldn't be loaded.
+#[derive(Debug)]
+pub enum SvgError {
+    Io(io::Error),
+    Xml(roxmltree::Error),
+    /// An attribute shapekit reads isn't valid SVG, like broken path data or a percentage length.
+    Invalid(String),
+}
+impl Display for SvgError {
+    fn fmt(&self, form: &mut Formatter) -> fmt::Result {
+        match self {
+            SvgError::Io(error) => write!(form, "couldn't read the SVG file: {}", error),
+            SvgError::Xml(error) => write!(form, "broken SVG file: {}", error),
+            SvgError::Invalid(reason) => write!(form, "invalid SVG: {}", reason),
+        }
+    }
+}
+impl Error for SvgError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SvgError::Io(error) => Some(error),
+            SvgError::Xml(error) => Some(error),
+            SvgError::Invalid(_) => None,
+        }
+    }
+}
+impl From<io::Error> for SvgError {
+    fn from(error: io::Error) -> Self {
+        SvgError::Io(error)
+    }
+}
+impl From<roxmltree::Error> for SvgError {
+    fn from(error: roxmltree::Error) -> Self {
+        SvgError::Xml(error)
+    }
+}
+
+/// One convex piece of an SVG element.
+#[derive(Debug, Clone)]
+pub struct SvgShape<T: Real = f32> {
+    pub shape: GenericShape<T>,
+    /// The `id` of the element, empty if it has none. Concave elements give several pieces with the same name.
+    pub name: String,
+}
+
+/// Reads collision shapes from the `<path>`, `<polygon>`, `<rect>` and `<circle>` elements of an SVG file,
+/// like outlines sketched in Inkscape.
+///
+/// Curves, arcs, circles and rounded corners are flattened into straight edges, `transform`s are applied
+/// and concave outlines are split into convex pieces. Every subpath of a path becomes a separate outline,
+/// so holes are filled in. Elements inside `<defs>`, masks, patterns and other unrendered containers are skipped.
+#[derive(Debug, Clone)]
+pub struct SvgLoader {
+    /// How far, in the units of each element, flattened curves may stray from the real ones. Must be positive.
+    pub tolerance: f64,
+    /// SVG points y down. Set this to negate every y coordinate for a y up world.
+    pub flip_y: bool,
+}
+impl Default for SvgLoader {
+    fn default() -> Self {
+        SvgLoader {
+            tolerance: 0.25,
+            flip_y: false,
+        }
+    }
+}
+impl SvgLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Loads an `.svg` file.
+    pub fn load<T: Real, P: AsRef<Path>>(&self, path: P) -> Result<Vec<SvgShape<T>>, SvgError> {
+        self.load_str(&fs::read_to_string(path)?)
+    }
+    /// Reads the contents of an SVG file.
+    pub fn load_str<T: Real>(&self, text: &str) -> Result<Vec<SvgShape<T>>, SvgError> {
+        if !(self.tolerance > 0. && self.tolerance.is_finite()) {
+            return Err(SvgError::Invalid(format!("the tolerance must be positive, not {}", self.tolerance)));
+        }
+        let document = Document::parse(text)?;
+        let root = document.root_element();
+        if root.tag_name().name() != "svg" {
+            return Err(SvgError::Invalid("the root element isn't <svg>".to_string()));
+        }
+        let mut shapes = Vec::new();
+        self.read(root, Matrix3::identity(), &mut shapes)?;
+        Ok(shapes)
+    }
+
+    fn read<T: Real>(&self, node: Node, transform: Matrix3<f64>, shapes: &mut Vec<SvgShape<T>>) -> Result<(), SvgError> {
+        for child in node.children().filter(Node::is_element) {
+            let transform = match child.attribute("transform") {
+                Some(text) => transform * parse_transform(text)?,
+                None => transform,
+            };
+            let outlines = match child.tag_name().name() {
+                "g" | "svg" | "a" | "switch" => {
+                    self.read(child, transform, shapes)?;
+                    continue;
+                }
+                "path" => path::parse(child.attribute("d").unwrap_or(""), self.tolerance)?,
+                "polygon" => {
+                    let numbers = path::numbers(child.attribute("points").unwrap_or(""))?;
+                    if numbers.len() % 2 != 0 {
+                        return Err(SvgError::Invalid(format!("odd number of coordinates in the points of {}", describe(child))));
+                    }
+                    vec![numbers.chunks(2).map(|pair| Point2::new(pair[0], pair[1])).collect()]
+                }
+                "rect" => self.rect(child)?.into_iter().collect(),
+                "circle" => {
+                    let center = Point2::new(length(child, "cx")?.unwrap_or(0.), length(child, "cy")?.unwrap_or(0.));
+                    let radius = length(child, "r")?.unwrap_or(0.);
+                    if radius <= 0. {
+                        continue;
+                    }
+                    let mut outline = Vec::new();
+                    path::arc(&mut outline, center, Vector2::new(radius, radius), 0., 0., 2. * PI, self.tolerance);
+                    vec![outline]
+                }
+                _ => continue,
+            };
+            let name = child.attribute("id").unwrap_or("");
+            for outline in outlines {
+                let outline = clean(outline.iter().map(|point| apply(&transform, point)).collect());
+                if outline.len() < 3 {
+                    continue;
+                }
+                for piece in GenericShape::convex_decomposition(outline) {
+                    shapes.push(SvgShape { shape: self.convert(&piece), name: name.to_string() });
+                }
+            }
+        }
+        Ok(())
+    }
+    /// The outline of a `<rect>`, with its corners rounded by `rx` and `ry`.
+    fn rect(&self, node: Node) -> Result<Option<Vec<Point2<f64>>>, SvgError> {
+        let (x, y) = (length(node, "x")?.unwrap_or(0.), length(node, "y")?.unwrap_or(0.));
+        let (width, height) = (length(node, "width")?.unwrap_or(0.), length(node, "height")?.unwrap_or(0.));
+        if width <= 0. || height <= 0. {
+            return Ok(None);
+        }
+        // A missing corner radius takes the value of the other one.
+        let (rx, ry) = match (length(node, "rx")?, length(node, "ry")?) {
+            (Some(rx), Some(ry)) => (rx, ry),
+            (Some(radius), None) | (None, Some(radius)) => (radius, radius),
+            (None, None) => (0., 0.),
+        };
+        let radii = Vector2::new(rx.max(0.).min(width / 2.), ry.max(0.).min(height / 2.));
+        let corners = [
+            (Point2::new(x + width - radii.x, y + radii.y), -FRAC_PI_2),
+            (Point2::new(x + width - radii.x, y + height - radii.y), 0.),
+            (Point2::new(x + radii.x, y + height - radii.y), FRAC_PI_2),
+            (Point2::new(x + radii.x, y + radii.y), PI),
+        ];
+        let mut outline = Vec::new();
+        for (center, start) in corners {
+            let (sin, cos) = f64::sin_cos(start);
+            outline.push(center + Vector2::new(radii.x * cos, radii.y * sin));
+            if radii.x > 0. && radii.y > 0. {
+                path::arc(&mut outline, center, radii, 0., start, FRAC_PI_2, self.tolerance);
+            }
+        }
+        Ok(Some(outline))
+    }
+    fn convert<T: Real>(&self, shape: &GenericShape<f64>) -> GenericShape<T> {
+        let sign = if self.flip_y { -1. } else { 1. };
+        GenericShape::new(shape.iter_points().map(|point| Point2::new(T::from_f64(point.x), T::from_f64(sign * point.y))))
+    }
+}
+
+/// Groups the pieces by the id of the element they came from, for looking shapes up by name.
+pub fn by_name<T: Real>(shapes: Vec<SvgShape<T>>) -> HashMap<String, Vec<GenericShape<T>>> {
+    let mut names: HashMap<String, Vec<GenericShape<T>>> = HashMap::new();
+    for SvgShape { shape, name } in shapes {
+        names.entry(name).or_default().push(shape);
+    }
+    names
+}
+
+/// Parses a `transform` attribute into a homogeneous matrix.
+fn parse_transform(text: &str) -> Result<Matrix3<f64>, SvgError> {
+    let invalid = || SvgError::Invalid(format!("bad transform {:?}", text));
+    let mut transform = Matrix3::identity();
+    let mut rest = text.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+    while !rest.is_empty() {
+        let open = rest.find('(').ok_or_else(invalid)?;
+        let close = rest.find(')').ok_or_else(invalid)?;
+        if close < open {
+            return Err(invalid());
+        }
+        let arguments = path::numbers(&rest[open + 1..close])?;
+        let step = match (rest[..open].trim(), arguments.as_slice()) {
+            ("matrix", &[a, b, c, d, e, f]) => Matrix3::new(a, c, e, b, d, f, 0., 0., 1.),
+            ("translate", &[x]) => translation(x, 0.),
+            ("translate", &[x, y]) => translation(x, y),
+            ("scale", &[factor]) => Matrix3::new(factor, 0., 0., 0., factor, 0., 0., 0., 1.),
+            ("scale", &[x, y]) => Matrix3::new(x, 0., 0., 0., y, 0., 0., 0., 1.),
+            ("rotate", &[angle]) => rotation(angle),
+            ("rotate", &[angle, x, y]) => translation(x, y) * rotation(angle) * translation(-x, -y),
+            ("skewX", &[angle]) => Matrix3::new(1., angle.to_radians().tan(), 0., 0., 1., 0., 0., 0., 1.),
+            ("skewY", &[angle]) => Matrix3::new(1., 0., 0., angle.to_radians().tan(), 1., 0., 0., 0., 1.),
+            _ => return Err(invalid()),
+        };
+        transform *= step;
+        rest = rest[close + 1..].trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+    }
+    Ok(transform)
+}
+
+fn translation(x: f64, y: f64) -> Matrix3<f64> {
+    Matrix3::new(1., 0., x, 0., 1., y, 0., 0., 1.)
+}
+
+fn rotation(degrees: f64) -> Matrix3<f64> {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    Matrix3::new(cos, -sin, 0., sin, cos, 0., 0., 0., 1.)
+}
+
+fn apply(transform: &Matrix3<f64>, point: &Point2<f64>) -> Point2<f64> {
+    Point2::new(
+        transform[(0, 0)] * point.x + transform[(0, 1)] * point.y + transform[(0, 2)],
+        transform[(1, 0)] * point.x + transform[(1, 1)] * point.y + transform[(1, 2)],
+    )
+}
+
+/// Drops repeated points, including a last point that closes the outline onto the first.
+fn clean(mut outline: Vec<Point2<f64>>) -> Vec<Point2<f64>> {
+    outline.dedup_by(|a, b| (*a - *b).norm() < 1e-9);
+    while outline.len() > 1 && (outline[0] - outline[outline.len() - 1]).norm() < 1e-9 {
+        outline.pop();
+    }
+    outline
+}
+
+/// A length attribute in user units, which may have a `px` suffix.
+fn length(node: Node, name: &str) -> Result<Option<f64>, SvgError> {
+    match node.attribute(name) {
+        None => Ok(None),
+        Some(text) => text
+            .trim()
+            .trim_end_matches("px")
+            .parse()
+            .map(Some)
+            .map_err(|_| SvgError::Invalid(format!("bad {} {:?} on {}", name, text, describe(node)))),
+    }
+}
+
+fn describe(node: Node) -> String {
+    match node.attribute("id") {
+        Some(id) => format!("<{} id={:?}>", node.tag_name().name(), id),
+        None => format!("<{}>", node.tag_name().name()),
+    }
+}
@@ -0,0 +1,281 @@
+use super::SvgError;
+use nalgebra::{Point2, Vector2};
+use std::f64::consts::{FRAC_PI_2, PI};
+
+/// Subdivision depth at which a curve counts as flat, whatever the tolerance.
+const MAX_DEPTH: u32 = 16;
+
+/// Flattens the `d` attribute of a `<path>` into one outline per subpath.
+pub(super) fn parse(data: &str, tolerance: f64) -> Result<Vec<Vec<Point2<f64>>>, SvgError> {
+    let mut scanner = Scanner::new(data);
+    let mut outlines = Vec::new();
+    let mut outline = Vec::new();
+    let (mut current, mut start) = (Point2::origin(), Point2::origin());
+    // The last control point of a curve and whether it was cubic, for the smooth `S` and `T` commands.
+    let mut control: Option<(bool, Point2<f64>)> = None;
+    let mut command = None;
+    while !scanner.done() {
+        let next = match (scanner.command(), command) {
+            (Some(next), _) => next,
+            // A moveto followed by more coordinates continues as lineto.
+            (None, Some(b'M')) => b'L',
+            (None, Some(b'm')) => b'l',
+            (None, Some(previous)) if !matches!(previous, b'Z' | b'z') => previous,
+            _ => return Err(scanner.error()),
+        };
+        command = Some(next);
+        let base = if next.is_ascii_lowercase() { current.coords } else { Vector2::zeros() };
+        let upper = next.to_ascii_uppercase();
+        if upper != b'M' && outline.is_empty() {
+            outline.push(current);
+        }
+        control = match upper {
+            b'M' => {
+                outlines.push(std::mem::take(&mut outline));
+                current = scanner.point()? + base;
+                start = current;
+                outline.push(current);
+                None
+            }
+            b'L' => {
+                current = scanner.point()? + base;
+                outline.push(current);
+                None
+            }
+            b'H' => {
+                current.x = scanner.number()? + base.x;
+                outline.push(current);
+                None
+            }
+            b'V' => {
+                current.y = scanner.number()? + base.y;
+                outline.push(current);
+                None
+            }
+            b'C' | b'S' => {
+                let first = match (upper, control) {
+                    (b'S', Some((true, previous))) => current + (current - previous),
+                    (b'S', _) => current,
+                    _ => scanner.point()? + base,
+                };
+                let second = scanner.point()? + base;
+                let end = scanner.point()? + base;
+                cubic(&mut outline, [current, first, second, end], tolerance, 0);
+                current = end;
+                Some((true, second))
+            }
+            b'Q' | b'T' => {
+                let middle = match (upper, control) {
+                    (b'T', Some((false, previous))) => current + (current - previous),
+                    (b'T', _) => current,
+                    _ => scanner.point()? + base,
+                };
+                let end = scanner.point()? + base;
+                // A quadratic curve is a cubic one with both control points two thirds of the way to its own.
+                let first = current + (middle - current) * 2. / 3.;
+                let second = end + (middle - end) * 2. / 3.;
+                cubic(&mut outline, [current, first, second, end], tolerance, 0);
+                current = end;
+                Some((false, middle))
+            }
+            b'A' => {
+                let radii = Vector2::new(scanner.number()?.abs(), scanner.number()?.abs());
+                let rotation = scanner.number()?.to_radians();
+                let (large, sweep) = (scanner.flag()?, scanner.flag()?);
+                let end = scanner.point()? + base;
+                endpoint_arc(&mut outline, current, end, radii, rotation, large, sweep, tolerance);
+                current = end;
+                None
+            }
+            b'Z' => {
+                outlines.push(std::mem::take(&mut outline));
+                current = start;
+                None
+            }
+            _ => return Err(scanner.error()),
+        };
+    }
+    outlines.push(outline);
+    Ok(outlines.into_iter().filter(|outline| outline.len() >= 3).collect())
+}
+
+/// The numbers of a `points` or `transform` attribute.
+pub(super) fn numbers(text: &str) -> Result<Vec<f64>, SvgError> {
+    let mut scanner = Scanner::new(text);
+    let mut numbers = Vec::new();
+    while !scanner.done() {
+        numbers.push(scanner.number()?);
+    }
+    Ok(numbers)
+}
+
+/// Appends the points of an elliptic arc after its start, which is at angle `start` on the ellipse.
+pub(super) fn arc(outline: &mut Vec<Point2<f64>>, center: Point2<f64>, radii: Vector2<f64>, rotation: f64, start: f64, sweep: f64, tolerance: f64) {
+    let radius = radii.x.max(radii.y);
+    // The widest angle whose chord stays within the tolerance, but at least four steps for a full turn.
+    let step = if tolerance < radius { 2. * (1. - tolerance / radius).acos() } else { PI };
+    // Like curves, arcs stop at as many pieces as `MAX_DEPTH` subdivisions give.
+    let steps = (sweep.abs() / step.min(FRAC_PI_2)).ceil().clamp(1., (1u32 << MAX_DEPTH) as f64) as usize;
+    let (sin, cos) = rotation.sin_cos();
+    for i in 1..=steps {
+        let (y, x) = (start + sweep * i as f64 / steps as f64).sin_cos();
+        let (x, y) = (radii.x * x, radii.y * y);
+        outline.push(center + Vector2::new(x * cos - y * sin, x * sin + y * cos));
+    }
+}
+
+/// Splits the curve in half until its control points are within the tolerance of its chord.
+fn cubic(outline: &mut Vec<Point2<f64>>, [a, b, c, d]: [Point2<f64>; 4], tolerance: f64, depth: u32) {
+    if depth >= MAX_DEPTH || (distance(b, a, d) <= tolerance && distance(c, a, d) <= tolerance) {
+        outline.push(d);
+        return;
+    }
+    let (ab, bc, cd) = (mid(a, b), mid(b, c), mid(c, d));
+    let (abc, bcd) = (mid(ab, bc), mid(bc, cd));
+    let middle = mid(abc, bcd);
+    cubic(outline, [a, ab, abc, middle], tolerance, depth + 1);
+    cubic(outline, [middle, bcd, cd, d], tolerance, depth + 1);
+}
+
+/// Converts an arc given by its end points into one around its center, as in the SVG implementation notes.
+#[allow(clippy::too_many_arguments)]
+fn endpoint_arc(outline: &mut Vec<Point2<f64>>, from: Point2<f64>, to: Point2<f64>, mut radii: Vector2<f64>, rotation: f64, large: bool, sweep: bool, tolerance: f64) {
+    if from == to {
+        return;
+    }
+    if radii.x == 0. || radii.y == 0. {
+        outline.push(to);
+        return;
+    }
+    let (sin, cos) = rotation.sin_cos();
+    let half = (from - to) / 2.;
+    let local = Vector2::new(cos * half.x + sin * half.y, cos * half.y - sin * half.x);
+    // Radii too small to reach the end point are scaled up until they just do.
+    let reach = (local.x / radii.x).powi(2) + (local.y / radii.y).powi(2);
+    if reach > 1. {
+        radii *= reach.sqrt();
+    }
+    let (rx, ry) = (radii.x * radii.x, radii.y * radii.y);
+    let numerator = rx * ry - rx * local.y * local.y - ry * local.x * local.x;
+    let denominator = rx * local.y * local.y + ry * local.x * local.x;
+    let sign = if large == sweep { -1. } else { 1. };
+    let factor = sign * (numerator / denominator).max(0.).sqrt();
+    let center = Vector2::new(factor * radii.x * local.y / radii.y, -factor * radii.y * local.x / radii.x);
+    let middle = Point2::from((from.coords + to.coords) / 2.);
+    let origin = middle + Vector2::new(cos * center.x - sin * center.y, sin * center.x + cos * center.y);
+    let angle = |vector: Vector2<f64>| (vector.y / radii.y).atan2(vector.x / radii.x);
+    let start = angle(local - center);
+    let mut delta = angle(-local - center) - start;
+    if sweep && delta < 0. {
+        delta += 2. * PI;
+    } else if !sweep && delta > 0. {
+        delta -= 2. * PI;
+    }
+    arc(outline, origin, radii, rotation, start, delta, tolerance);
+    // Land exactly on the end point, which the next command starts from.
+    if let Some(last) = outline.last_mut() {
+        *last = to;
+    }
+}
+
+fn mid(a: Point2<f64>, b: Point2<f64>) -> Point2<f64> {
+    Point2::from((a.coords + b.coords) / 2.)
+}
+
+/// Distance from `point` to the segment from `a` to `b`.
+fn distance(point: Point2<f64>, a: Point2<f64>, b: Point2<f64>) -> f64 {
+    let line = b - a;
+    let length = line.norm_squared();
+    let along = if length == 0. { 0. } else { ((point - a).dot(&line) / length).clamp(0., 1.) };
+    (point - (a + line * along)).norm()
+}
+
+/// Reads SVG's compact number syntax, where `1-2.5.5` is three numbers and commas are optional.
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+impl<'a> Scanner<'a> {
+    fn new(text: &'a str) -> Self {
+        let mut scanner = Scanner { bytes: text.as_bytes(), at: 0 };
+        scanner.skip();
+        scanner
+    }
+    fn done(&self) -> bool {
+        self.at >= self.bytes.len()
+    }
+    fn error(&self) -> SvgError {
+        SvgError::Invalid(format!("unexpected character at {} in {:?}", self.at, String::from_utf8_lossy(self.bytes)))
+    }
+    /// Skips whitespace and at most one comma.
+    fn skip(&mut self) {
+        let mut comma = false;
+        while let Some(&byte) = self.bytes.get(self.at) {
+            match byte {
+                b' ' | b'\t' | b'\n' | b'\r' => {}
+                b',' if !comma => comma = true,
+                _ => break,
+            }
+            self.at += 1;
+        }
+    }
+    fn command(&mut self) -> Option<u8> {
+        let byte = *self.bytes.get(self.at)?;
+        if byte.is_ascii_alphabetic() && !matches!(byte, b'e' | b'E') {
+            self.at += 1;
+            self.skip();
+            Some(byte)
+        } else {
+            None
+        }
+    }
+    fn number(&mut self) -> Result<f64, SvgError> {
+        let start = self.at;
+        let digits = |scanner: &mut Self| {
+            let from = scanner.at;
+            while scanner.bytes.get(scanner.at).is_some_and(u8::is_ascii_digit) {
+                scanner.at += 1;
+            }
+            scanner.at - from
+        };
+        if matches!(self.bytes.get(self.at), Some(b'+' | b'-')) {
+            self.at += 1;
+        }
+        let mut count = digits(self);
+        if self.bytes.get(self.at) == Some(&b'.') {
+            self.at += 1;
+            count += digits(self);
+        }
+        if count == 0 {
+            self.at = start;
+            return Err(self.error());
+        }
+        if matches!(self.bytes.get(self.at), Some(b'e' | b'E')) {
+            let mantissa = self.at;
+            self.at += 1;
+            if matches!(self.bytes.get(self.at), Some(b'+' | b'-')) {
+                self.at += 1;
+            }
+            if digits(self) == 0 {
+                self.at = mantissa;
+            }
+        }
+        let number = std::str::from_utf8(&self.bytes[start..self.at]).ok().and_then(|text| text.parse().ok());
+        self.skip();
+        number.ok_or_else(|| self.error())
+    }
+    /// Arc flags are a single digit, which may run straight into the next number.
+    fn flag(&mut self) -> Result<bool, SvgError> {
+        let flag = match self.bytes.get(self.at) {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(self.error()),
+        };
+        self.at += 1;
+        self.skip();
+        Ok(flag)
+    }
+    fn point(&mut self) -> Result<Point2<f64>, SvgError> {
+        Ok(Point2::new(self.number()?, self.number()?))
+    }
+}
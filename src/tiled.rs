@@ -0,0 +1,510 @@
+//! Collision shapes from [Tiled](https://www.mapeditor.org) maps, saved as TMX or JSON.
+mod tmx;
+mod json;
+use crate::processing::world::{CollisionWorld, ALL_LAYERS};
+use crate::scalar::Real;
+use crate::shape::GenericShape;
+use base64::Engine;
+use nalgebra::{Point2, Vector2};
+#[cfg(feature = "specs")]
+use crate::shape::Shape;
+#[cfg(feature = "specs")]
+use specs::{world::Builder, Entity, World, WorldExt};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+const FLIP_HORIZONTAL: u32 = 0x8000_0000;
+const FLIP_VERTICAL: u32 = 0x4000_0000;
+const FLIP_DIAGONAL: u32 = 0x2000_0000;
+const GID_MASK: u32 = 0x0fff_ffff;
+
+/// Why a map couldn't be loaded.
+#[derive(Debug)]
+pub enum TiledError {
+    Io(io::Error),
+    Xml(roxmltree::Error),
+    Json(serde_json::Error),
+    /// The file parsed, but isn't a map Tiled would write.
+    Invalid(String),
+    /// A valid map using something shapekit can't read, like an isometric orientation.
+    Unsupported(String),
+}
+impl Display for TiledError {
+    fn fmt(&self, form: &mut Formatter) -> fmt::Result {
+        match self {
+            TiledError::Io(error) => write!(form, "couldn't read the map: {}", error),
+            TiledError::Xml(error) => write!(form, "broken TMX file: {}", error),
+            TiledError::Json(error) => write!(form, "broken JSON map: {}", error),
+            TiledError::Invalid(reason) => write!(form, "invalid map: {}", reason),
+            TiledError::Unsupported(what) => write!(form, "unsupported map: {}", what),
+        }
+    }
+}
+impl Error for TiledError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TiledError::Io(error) => Some(error),
+            TiledError::Xml(error) => Some(error),
+            TiledError::Json(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+impl From<io::Error> for TiledError {
+    fn from(error: io::Error) -> Self {
+        TiledError::Io(error)
+    }
+}
+impl From<roxmltree::Error> for TiledError {
+    fn from(error: roxmltree::Error) -> Self {
+        TiledError::Xml(error)
+    }
+}
+impl From<serde_json::Error> for TiledError {
+    fn from(error: serde_json::Error) -> Self {
+        TiledError::Json(error)
+    }
+}
+
+/// One convex piece of a map's collision geometry.
+#[derive(Debug, Clone)]
+pub struct TiledCollider<T: Real = f32> {
+    pub shape: GenericShape<T>,
+    /// Name of the layer it came from.
+    pub layer: String,
+    /// Name of the object, empty for tiles.
+    pub name: String,
+    /// Class (formerly type) of the object, or of the tile for tile collisions.
+    pub class: String,
+}
+
+/// Reads colliders from the object layers and tile collisions of orthogonal Tiled maps.
+///
+/// Rectangles, ellipses, polygons and tile objects become shapes, concave polygons being split into
+/// convex pieces. Polylines are closed into polygons. Point and text objects are skipped.
+/// Every tile layer contributes the collision objects set on its tiles in the tileset editor.
+#[derive(Debug, Clone)]
+pub struct TiledLoader {
+    /// Number of edges used for ellipse objects.
+    pub ellipse_segments: usize,
+    /// Tiled points y down. Set this to negate every y coordinate for a y up world.
+    pub flip_y: bool,
+    /// Joins neighbouring tiles with the same rectangular collision into one larger rectangle,
+    /// which keeps the shape count down and stops objects from catching on the seams.
+    ///
+    /// Only tiles whose collision is a single axis aligned rectangle of the same size and class are merged,
+    /// along the axes it spans the whole tile in. Slopes and other polygons stay one collider per tile.
+    pub merge_tiles: bool,
+}
+impl Default for TiledLoader {
+    fn default() -> Self {
+        TiledLoader {
+            ellipse_segments: 16,
+            flip_y: false,
+            merge_tiles: true,
+        }
+    }
+}
+impl TiledLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Loads a `.tmx` file, or a JSON map (`.tmj` or `.json`). External tilesets are looked up next to it.
+    pub fn load<T: Real, P: AsRef<Path>>(&self, path: P) -> Result<Vec<TiledCollider<T>>, TiledError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("tmx") => self.load_tmx(&text, dir),
+            _ => self.load_json(&text, dir),
+        }
+    }
+    /// Reads the contents of a TMX file, resolving external tilesets against `dir`.
+    pub fn load_tmx<T: Real>(&self, text: &str, dir: &Path) -> Result<Vec<TiledCollider<T>>, TiledError> {
+        let map = tmx::parse(text, dir)?;
+        map.check_sizes()?;
+        Ok(self.colliders(&map))
+    }
+    /// Reads the contents of a JSON map, resolving external tilesets against `dir`.
+    pub fn load_json<T: Real>(&self, text: &str, dir: &Path) -> Result<Vec<TiledCollider<T>>, TiledError> {
+        let map = json::parse(text, dir)?;
+        map.check_sizes()?;
+        Ok(self.colliders(&map))
+    }
+
+    fn colliders<T: Real>(&self, map: &Map) -> Vec<TiledCollider<T>> {
+        let mut colliders = Vec::new();
+        for layer in &map.layers {
+            let mut push = |outline: Vec<Point2<f64>>, name: &str, class: &str| {
+                for piece in GenericShape::convex_decomposition(outline.iter().map(|point| point + layer.offset)) {
+                    colliders.push(TiledCollider {
+                        shape: self.convert(&piece),
+                        layer: layer.name.clone(),
+                        name: name.to_string(),
+                        class: class.to_string(),
+                    });
+                }
+            };
+            match &layer.content {
+                LayerContent::Objects(objects) => {
+                    for object in objects {
+                        let tile = object.gid.and_then(|gid| map.tile(gid));
+                        let class = match tile {
+                            Some((_, tile)) if object.class.is_empty() => &tile.class,
+                            _ => &object.class,
+                        };
+                        for outline in self.object_outlines(object, map) {
+                            push(outline, &object.name, class);
+                        }
+                    }
+                }
+                LayerContent::Tiles(chunks) => {
+                    let cells = chunks.iter().flat_map(|chunk| {
+                        chunk.gids.iter().enumerate().filter(|(_, &gid)| gid & GID_MASK != 0).map(move |(i, &gid)| {
+                            ((chunk.x + (i % chunk.width) as i64, chunk.y + (i / chunk.width) as i64), gid)
+                        })
+                    });
+                    let mut rects: HashMap<(i64, i64), RectKey> = HashMap::new();
+                    for ((column, row), gid) in cells {
+                        let (set, tile) = match map.tile(gid) {
+                            Some(found) => found,
+                            None => continue,
+                        };
+                        let outlines = self.tile_outlines(set, tile, gid);
+                        if let Some(key) = self.rect_key(map, set, tile, &outlines) {
+                            rects.insert((column, row), key);
+                            continue;
+                        }
+                        // Tiles can be taller than the grid, in which case they stick out above their cell.
+                        let origin = Vector2::new(column as f64 * map.tile_width, (row + 1) as f64 * map.tile_height - set.tile_height);
+                        for outline in outlines {
+                            push(outline.iter().map(|point| point + origin).collect(), "", &tile.class);
+                        }
+                    }
+                    for (rect, class) in merge_rects(&rects, map) {
+                        push(rect, "", &class);
+                    }
+                }
+            }
+        }
+        colliders
+    }
+
+    /// Outlines of an object, in map pixels.
+    fn object_outlines(&self, object: &Object, map: &Map) -> Vec<Vec<Point2<f64>>> {
+        let local = match object.gid {
+            // Tile objects are anchored at their bottom left corner and scaled to their size.
+            Some(gid) => match map.tile(gid) {
+                Some((set, tile)) => {
+                    let scale = Vector2::new(object.width / set.tile_width, object.height / set.tile_height);
+                    self.tile_outlines(set, tile, gid)
+                        .into_iter()
+                        .map(|outline| outline.iter().map(|point| Point2::new(point.x * scale.x, point.y * scale.y - object.height)).collect())
+                        .collect()
+                }
+                None => Vec::new(),
+            },
+            None => self.outline(object).into_iter().collect(),
+        };
+        local.into_iter().map(|outline| place(object, &outline)).collect()
+    }
+
+    /// Outline of a plain object relative to its position, before rotation.
+    fn outline(&self, object: &Object) -> Option<Vec<Point2<f64>>> {
+        let sized = object.width > 0. && object.height > 0.;
+        match &object.kind {
+            ObjectKind::Rect if sized => Some(vec![
+                Point2::new(0., 0.),
+                Point2::new(object.width, 0.),
+                Point2::new(object.width, object.height),
+                Point2::new(0., object.height),
+            ]),
+            ObjectKind::Ellipse if sized => {
+                let center = Vector2::new(object.width, object.height) / 2.;
+                let ellipse = GenericShape::<f64>::ellipse(center.x, center.y, self.ellipse_segments);
+                Some(ellipse.iter_points().map(|point| point + center).collect())
+            }
+            ObjectKind::Polygon(points) | ObjectKind::Polyline(points) => Some(points.clone()),
+            _ => None,
+        }
+    }
+
+    /// Collision outlines of a tile relative to the top left of its image, with the flips stored in `gid` applied.
+    fn tile_outlines(&self, set: &Tileset, tile: &Tile, gid: u32) -> Vec<Vec<Point2<f64>>> {
+        let flip = |point: &Point2<f64>| {
+            // Tiled swaps the axes first, then mirrors.
+            let mut point = if gid & FLIP_DIAGONAL != 0 { Point2::new(point.y, point.x) } else { *point };
+            if gid & FLIP_HORIZONTAL != 0 {
+                point.x = set.tile_width - point.x;
+            }
+            if gid & FLIP_VERTICAL != 0 {
+                point.y = set.tile_height - point.y;
+            }
+            point
+        };
+        tile.objects
+            .iter()
+            .filter_map(|object| Some(place(object, &self.outline(object)?)))
+            .map(|outline| outline.iter().map(flip).collect())
+            .collect()
+    }
+
+    /// Describes a tile whose whole collision is one axis aligned rectangle, which lets it merge with its neighbours.
+    fn rect_key(&self, map: &Map, set: &Tileset, tile: &Tile, outlines: &[Vec<Point2<f64>>]) -> Option<RectKey> {
+        if !self.merge_tiles || set.tile_width != map.tile_width || set.tile_height != map.tile_height {
+            return None;
+        }
+        let outline = match outlines {
+            [outline] if outline.len() == 4 => outline,
+            _ => return None,
+        };
+        let (min, max) = outline.iter().fold((outline[0], outline[0]), |(min, max), point| {
+            (Point2::new(min.x.min(point.x), min.y.min(point.y)), Point2::new(max.x.max(point.x), max.y.max(point.y)))
+        });
+        let axis_aligned = outline.iter().all(|point| (point.x == min.x || point.x == max.x) && (point.y == min.y || point.y == max.y));
+        if !axis_aligned || min.x == max.x || min.y == max.y {
+            return None;
+        }
+        Some(RectKey {
+            bounds: [min.x.to_bits(), min.y.to_bits(), max.x.to_bits(), max.y.to_bits()],
+            class: tile.class.clone(),
+        })
+    }
+
+    fn convert<T: Real>(&self, shape: &GenericShape<f64>) -> GenericShape<T> {
+        let sign = if self.flip_y { -1. } else { 1. };
+        GenericShape::new(shape.iter_points().map(|point| Point2::new(T::from_f64(point.x), T::from_f64(sign * point.y))))
+    }
+}
+
+/// Puts every collider on every layer of a static world, keyed by its index in `colliders`.
+pub fn collision_world<T: Real>(colliders: &[TiledCollider<T>]) -> CollisionWorld<usize, T> {
+    let mut world = CollisionWorld::new();
+    for (index, collider) in colliders.iter().enumerate() {
+        world.insert(index, collider.shape.clone(), ALL_LAYERS);
+    }
+    world
+}
+
+/// Creates an entity with a `Shape` for every collider. Without a `Transform` they stay where the map put them.
+#[cfg(feature = "specs")]
+pub fn create_entities(world: &mut World, colliders: Vec<TiledCollider>) -> Vec<Entity> {
+    world.register::<Shape>();
+    colliders
+        .into_iter()
+        .map(|collider| world.create_entity().with(collider.shape).build())
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct RectKey {
+    bounds: [u64; 4],
+    class: String,
+}
+impl RectKey {
+    fn bounds(&self) -> [f64; 4] {
+        let [x0, y0, x1, y1] = self.bounds;
+        [f64::from_bits(x0), f64::from_bits(y0), f64::from_bits(x1), f64::from_bits(y1)]
+    }
+}
+
+/// Rotates an outline around the position of its object, then moves it there.
+fn place(object: &Object, outline: &[Point2<f64>]) -> Vec<Point2<f64>> {
+    let (sin, cos) = object.rotation.to_radians().sin_cos();
+    outline
+        .iter()
+        .map(|point| Point2::new(object.x + point.x * cos - point.y * sin, object.y + point.x * sin + point.y * cos))
+        .collect()
+}
+
+/// Greedily covers the cells with rectangles, growing each one right and then down.
+/// Rectangles only grow along an axis where they fill the whole tile, so the result has no gaps.
+fn merge_rects(cells: &HashMap<(i64, i64), RectKey>, map: &Map) -> Vec<(Vec<Point2<f64>>, String)> {
+    let mut order: Vec<&(i64, i64)> = cells.keys().collect();
+    order.sort_by_key(|&&(column, row)| (row, column));
+    let mut used = HashSet::new();
+    let mut rects = Vec::new();
+    for &(column, row) in order {
+        if used.contains(&(column, row)) {
+            continue;
+        }
+        let key = &cells[&(column, row)];
+        let [x0, y0, x1, y1] = key.bounds();
+        let free = |cell: (i64, i64)| !used.contains(&cell) && cells.get(&cell) == Some(key);
+        let mut width = 1;
+        if x0 == 0. && x1 == map.tile_width {
+            while free((column + width, row)) {
+                width += 1;
+            }
+        }
+        let mut height = 1;
+        if y0 == 0. && y1 == map.tile_height {
+            while (column..column + width).all(|x| free((x, row + height))) {
+                height += 1;
+            }
+        }
+        for x in column..column + width {
+            for y in row..row + height {
+                used.insert((x, y));
+            }
+        }
+        let min = Point2::new(column as f64 * map.tile_width + x0, row as f64 * map.tile_height + y0);
+        let max = Point2::new((column + width - 1) as f64 * map.tile_width + x1, (row + height - 1) as f64 * map.tile_height + y1);
+        let outline = vec![min, Point2::new(max.x, min.y), max, Point2::new(min.x, max.y)];
+        rects.push((outline, key.class.clone()));
+    }
+    rects
+}
+
+/// What the TMX and JSON readers agree on.
+#[derive(Debug)]
+struct Map {
+    tile_width: f64,
+    tile_height: f64,
+    /// Sorted by `first_gid`.
+    tilesets: Vec<Tileset>,
+    /// Flattened out of their groups, with the group offsets added.
+    layers: Vec<Layer>,
+}
+impl Map {
+    fn tile(&self, gid: u32) -> Option<(&Tileset, &Tile)> {
+        let gid = gid & GID_MASK;
+        let set = self.tilesets.iter().rev().find(|set| set.first_gid <= gid)?;
+        Some((set, set.tiles.get(&(gid - set.first_gid))?))
+    }
+    /// Tile objects are scaled by the tile size, so a missing or zero size would turn them into NaN.
+    fn check_sizes(&self) -> Result<(), TiledError> {
+        let sizes = std::iter::once(("the map".to_string(), self.tile_width, self.tile_height)).chain(
+            self.tilesets
+                .iter()
+                .map(|set| (format!("the tileset at gid {}", set.first_gid), set.tile_width, set.tile_height)),
+        );
+        for (owner, width, height) in sizes {
+            if !(width > 0. && height > 0.) {
+                return Err(TiledError::Invalid(format!("{} has a tile size of {}x{}", owner, width, height)));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Tileset {
+    first_gid: u32,
+    tile_width: f64,
+    tile_height: f64,
+    /// Only the tiles that have collision objects, by local id.
+    tiles: HashMap<u32, Tile>,
+}
+
+#[derive(Debug)]
+struct Tile {
+    class: String,
+    objects: Vec<Object>,
+}
+
+#[derive(Debug)]
+struct Object {
+    name: String,
+    class: String,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    /// Clockwise, in degrees.
+    rotation: f64,
+    gid: Option<u32>,
+    kind: ObjectKind,
+}
+
+#[derive(Debug)]
+enum ObjectKind {
+    Rect,
+    Ellipse,
+    Polygon(Vec<Point2<f64>>),
+    Polyline(Vec<Point2<f64>>),
+    /// Points and text, which have no area.
+    Other,
+}
+
+#[derive(Debug)]
+struct Layer {
+    name: String,
+    offset: Vector2<f64>,
+    content: LayerContent,
+}
+
+#[derive(Debug)]
+enum LayerContent {
+    Tiles(Vec<Chunk>),
+    Objects(Vec<Object>),
+}
+
+/// A block of tile layer data. Finite maps have a single chunk covering the layer.
+#[derive(Debug)]
+struct Chunk {
+    x: i64,
+    y: i64,
+    width: usize,
+    gids: Vec<u32>,
+}
+impl Chunk {
+    fn new(x: i64, y: i64, width: usize, gids: Vec<u32>) -> Result<Chunk, TiledError> {
+        if width == 0 && !gids.is_empty() {
+            return Err(TiledError::Invalid("tile layer data without a width".to_string()));
+        }
+        Ok(Chunk { x, y, width, gids })
+    }
+}
+
+fn check_orientation(orientation: &str) -> Result<(), TiledError> {
+    match orientation {
+        "" | "orthogonal" => Ok(()),
+        other => Err(TiledError::Unsupported(format!("{} orientation", other))),
+    }
+}
+
+fn load_tileset(path: &Path, first_gid: u32) -> Result<Tileset, TiledError> {
+    let text = fs::read_to_string(path)?;
+    let mut set = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("tsx") => tmx::parse_tileset(&text)?,
+        _ => json::parse_tileset(&text)?,
+    };
+    set.first_gid = first_gid;
+    Ok(set)
+}
+
+/// Decodes `csv` or `base64` tile layer data, which may be zlib or gzip compressed.
+fn decode_gids(data: &str, encoding: &str, compression: &str) -> Result<Vec<u32>, TiledError> {
+    match encoding {
+        "csv" => data
+            .split(',')
+            .map(|gid| gid.trim().parse().map_err(|_| TiledError::Invalid(format!("bad tile id {:?}", gid.trim()))))
+            .collect(),
+        "base64" => {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(data.trim())
+                .map_err(|error| TiledError::Invalid(error.to_string()))?;
+            let mut raw = Vec::new();
+            match compression {
+                "" => raw = bytes,
+                "zlib" => {
+                    flate2::read::ZlibDecoder::new(&bytes[..]).read_to_end(&mut raw)?;
+                }
+                "gzip" => {
+                    flate2::read::GzDecoder::new(&bytes[..]).read_to_end(&mut raw)?;
+                }
+                other => return Err(TiledError::Unsupported(format!("{} compression", other))),
+            }
+            if raw.len() % 4 != 0 {
+                return Err(TiledError::Invalid(format!("{} bytes of tile layer data aren't a whole number of tile ids", raw.len())));
+            }
+            Ok(raw.chunks_exact(4).map(|gid| u32::from_le_bytes([gid[0], gid[1], gid[2], gid[3]])).collect())
+        }
+        other => Err(TiledError::Unsupported(format!("{} encoding", other))),
+    }
+}
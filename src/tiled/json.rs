@@ -0,0 +1,228 @@
+use super::*;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct JsonMap {
+    #[serde(default)]
+    orientation: String,
+    tilewidth: f64,
+    tileheight: f64,
+    #[serde(default)]
+    tilesets: Vec<JsonTileset>,
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+}
+
+#[derive(Deserialize)]
+struct JsonTileset {
+    #[serde(default)]
+    firstgid: u32,
+    source: Option<String>,
+    #[serde(default)]
+    tilewidth: f64,
+    #[serde(default)]
+    tileheight: f64,
+    #[serde(default)]
+    tiles: Vec<JsonTile>,
+}
+
+#[derive(Deserialize)]
+struct JsonTile {
+    id: u32,
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    class: String,
+    objectgroup: Option<JsonObjectGroup>,
+}
+
+#[derive(Deserialize)]
+struct JsonObjectGroup {
+    #[serde(default)]
+    objects: Vec<JsonObject>,
+}
+
+#[derive(Deserialize)]
+struct JsonLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    offsetx: f64,
+    #[serde(default)]
+    offsety: f64,
+    #[serde(default)]
+    width: usize,
+    data: Option<JsonData>,
+    #[serde(default)]
+    encoding: String,
+    #[serde(default)]
+    compression: String,
+    #[serde(default)]
+    chunks: Vec<JsonChunk>,
+    #[serde(default)]
+    objects: Vec<JsonObject>,
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonData {
+    Gids(Vec<u32>),
+    Encoded(String),
+}
+
+#[derive(Deserialize)]
+struct JsonChunk {
+    x: i64,
+    y: i64,
+    width: usize,
+    data: JsonData,
+}
+
+#[derive(Deserialize)]
+struct JsonObject {
+    #[serde(default)]
+    name: String,
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    class: String,
+    #[serde(default)]
+    x: f64,
+    #[serde(default)]
+    y: f64,
+    #[serde(default)]
+    width: f64,
+    #[serde(default)]
+    height: f64,
+    #[serde(default)]
+    rotation: f64,
+    gid: Option<u32>,
+    #[serde(default)]
+    ellipse: bool,
+    #[serde(default)]
+    point: bool,
+    #[serde(default)]
+    text: Option<serde_json::Value>,
+    polygon: Option<Vec<JsonPoint>>,
+    polyline: Option<Vec<JsonPoint>>,
+}
+
+#[derive(Deserialize)]
+struct JsonPoint {
+    x: f64,
+    y: f64,
+}
+
+pub(super) fn parse(text: &str, dir: &Path) -> Result<Map, TiledError> {
+    let map: JsonMap = serde_json::from_str(text)?;
+    check_orientation(&map.orientation)?;
+    let mut tilesets = map
+        .tilesets
+        .into_iter()
+        .map(|set| match &set.source {
+            Some(source) => load_tileset(&dir.join(source), set.firstgid),
+            None => Ok(tileset(set)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    tilesets.sort_by_key(|set| set.first_gid);
+    let mut layers = Vec::new();
+    read_layers(map.layers, Vector2::zeros(), &mut layers)?;
+    Ok(Map {
+        tile_width: map.tilewidth,
+        tile_height: map.tileheight,
+        tilesets,
+        layers,
+    })
+}
+
+/// Reads the contents of an external `.tsj` or `.json` tileset.
+pub(super) fn parse_tileset(text: &str) -> Result<Tileset, TiledError> {
+    Ok(tileset(serde_json::from_str(text)?))
+}
+
+fn tileset(set: JsonTileset) -> Tileset {
+    Tileset {
+        first_gid: set.firstgid,
+        tile_width: set.tilewidth,
+        tile_height: set.tileheight,
+        tiles: set
+            .tiles
+            .into_iter()
+            .filter_map(|tile| {
+                let objects = tile.objectgroup?.objects.into_iter().map(object).collect();
+                Some((tile.id, Tile { class: class(tile.class, tile.kind), objects }))
+            })
+            .collect(),
+    }
+}
+
+fn read_layers(json: Vec<JsonLayer>, offset: Vector2<f64>, layers: &mut Vec<Layer>) -> Result<(), TiledError> {
+    for layer in json {
+        let offset = offset + Vector2::new(layer.offsetx, layer.offsety);
+        let content = match layer.kind.as_str() {
+            "tilelayer" => {
+                let (encoding, compression) = (&layer.encoding, &layer.compression);
+                let gids = |data: JsonData| match data {
+                    JsonData::Gids(gids) => Ok(gids),
+                    JsonData::Encoded(text) => decode_gids(&text, encoding, compression),
+                };
+                // Infinite maps split their layers into chunks.
+                let mut chunks = Vec::new();
+                if let Some(data) = layer.data {
+                    chunks.push(Chunk::new(0, 0, layer.width, gids(data)?)?);
+                }
+                for chunk in layer.chunks {
+                    chunks.push(Chunk::new(chunk.x, chunk.y, chunk.width, gids(chunk.data)?)?);
+                }
+                LayerContent::Tiles(chunks)
+            }
+            "objectgroup" => LayerContent::Objects(layer.objects.into_iter().map(object).collect()),
+            "group" => {
+                read_layers(layer.layers, offset, layers)?;
+                continue;
+            }
+            _ => continue,
+        };
+        layers.push(Layer { name: layer.name, offset, content });
+    }
+    Ok(())
+}
+
+fn object(object: JsonObject) -> Object {
+    let points = |points: Vec<JsonPoint>| points.into_iter().map(|point| Point2::new(point.x, point.y)).collect();
+    let kind = if let Some(polygon) = object.polygon {
+        ObjectKind::Polygon(points(polygon))
+    } else if let Some(polyline) = object.polyline {
+        ObjectKind::Polyline(points(polyline))
+    } else if object.ellipse {
+        ObjectKind::Ellipse
+    } else if object.point || object.text.is_some() {
+        ObjectKind::Other
+    } else {
+        ObjectKind::Rect
+    };
+    Object {
+        name: object.name,
+        class: class(object.class, object.kind),
+        x: object.x,
+        y: object.y,
+        width: object.width,
+        height: object.height,
+        rotation: object.rotation,
+        gid: object.gid,
+        kind,
+    }
+}
+
+/// Tiled 1.9 renamed `type` to `class`.
+fn class(class: String, kind: String) -> String {
+    if class.is_empty() {
+        kind
+    } else {
+        class
+    }
+}
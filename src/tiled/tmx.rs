@@ -0,0 +1,170 @@
+use super::*;
+use roxmltree::{Document, Node};
+use std::str::FromStr;
+
+pub(super) fn parse(text: &str, dir: &Path) -> Result<Map, TiledError> {
+    let document = Document::parse(text)?;
+    let root = document.root_element();
+    if !root.has_tag_name("map") {
+        return Err(TiledError::Invalid("the root element isn't <map>".to_string()));
+    }
+    check_orientation(root.attribute("orientation").unwrap_or(""))?;
+    let mut tilesets = Vec::new();
+    for node in root.children().filter(|node| node.has_tag_name("tileset")) {
+        let first_gid = number(node, "firstgid")?;
+        tilesets.push(match node.attribute("source") {
+            Some(source) => load_tileset(&dir.join(source), first_gid)?,
+            None => Tileset {
+                first_gid,
+                ..tileset(node)?
+            },
+        });
+    }
+    tilesets.sort_by_key(|set| set.first_gid);
+    let mut layers = Vec::new();
+    read_layers(root, Vector2::zeros(), &mut layers)?;
+    Ok(Map {
+        tile_width: number(root, "tilewidth")?,
+        tile_height: number(root, "tileheight")?,
+        tilesets,
+        layers,
+    })
+}
+
+/// Reads the contents of an external `.tsx` tileset.
+pub(super) fn parse_tileset(text: &str) -> Result<Tileset, TiledError> {
+    let document = Document::parse(text)?;
+    let root = document.root_element();
+    if !root.has_tag_name("tileset") {
+        return Err(TiledError::Invalid("the root element isn't <tileset>".to_string()));
+    }
+    tileset(root)
+}
+
+fn tileset(node: Node) -> Result<Tileset, TiledError> {
+    let mut tiles = HashMap::new();
+    for tile in node.children().filter(|child| child.has_tag_name("tile")) {
+        let objects = match tile.children().find(|child| child.has_tag_name("objectgroup")) {
+            Some(group) => objects(group)?,
+            None => continue,
+        };
+        tiles.insert(number(tile, "id")?, Tile { class: class(tile), objects });
+    }
+    Ok(Tileset {
+        first_gid: 0,
+        tile_width: number(node, "tilewidth")?,
+        tile_height: number(node, "tileheight")?,
+        tiles,
+    })
+}
+
+fn read_layers(parent: Node, offset: Vector2<f64>, layers: &mut Vec<Layer>) -> Result<(), TiledError> {
+    for node in parent.children().filter(Node::is_element) {
+        let offset = offset + Vector2::new(number_or(node, "offsetx", 0.)?, number_or(node, "offsety", 0.)?);
+        let content = match node.tag_name().name() {
+            "layer" => LayerContent::Tiles(chunks(node)?),
+            "objectgroup" => LayerContent::Objects(objects(node)?),
+            "group" => {
+                read_layers(node, offset, layers)?;
+                continue;
+            }
+            _ => continue,
+        };
+        layers.push(Layer {
+            name: node.attribute("name").unwrap_or("").to_string(),
+            offset,
+            content,
+        });
+    }
+    Ok(())
+}
+
+fn chunks(layer: Node) -> Result<Vec<Chunk>, TiledError> {
+    let data = match layer.children().find(|child| child.has_tag_name("data")) {
+        Some(data) => data,
+        None => return Ok(Vec::new()),
+    };
+    let encoding = data.attribute("encoding").unwrap_or("");
+    let compression = data.attribute("compression").unwrap_or("");
+    let gids = |node: Node| match encoding {
+        // Without an encoding every tile is its own element.
+        "" => node
+            .children()
+            .filter(|child| child.has_tag_name("tile"))
+            .map(|tile| number_or(tile, "gid", 0))
+            .collect(),
+        _ => decode_gids(node.text().unwrap_or(""), encoding, compression),
+    };
+    // Infinite maps split their layers into chunks.
+    let chunks: Vec<Node> = data.children().filter(|child| child.has_tag_name("chunk")).collect();
+    if chunks.is_empty() {
+        return Ok(vec![Chunk::new(0, 0, number(layer, "width")?, gids(data)?)?]);
+    }
+    chunks
+        .into_iter()
+        .map(|chunk| Chunk::new(number(chunk, "x")?, number(chunk, "y")?, number(chunk, "width")?, gids(chunk)?))
+        .collect()
+}
+
+fn objects(group: Node) -> Result<Vec<Object>, TiledError> {
+    group
+        .children()
+        .filter(|child| child.has_tag_name("object"))
+        .map(|node| {
+            let points = |shape: Node| points(shape.attribute("points").unwrap_or(""));
+            let kind = match node.children().find(Node::is_element) {
+                Some(shape) if shape.has_tag_name("ellipse") => ObjectKind::Ellipse,
+                Some(shape) if shape.has_tag_name("polygon") => ObjectKind::Polygon(points(shape)?),
+                Some(shape) if shape.has_tag_name("polyline") => ObjectKind::Polyline(points(shape)?),
+                Some(shape) if shape.has_tag_name("point") || shape.has_tag_name("text") => ObjectKind::Other,
+                _ => ObjectKind::Rect,
+            };
+            Ok(Object {
+                name: node.attribute("name").unwrap_or("").to_string(),
+                class: class(node),
+                x: number_or(node, "x", 0.)?,
+                y: number_or(node, "y", 0.)?,
+                width: number_or(node, "width", 0.)?,
+                height: number_or(node, "height", 0.)?,
+                rotation: number_or(node, "rotation", 0.)?,
+                gid: node.attribute("gid").map(|_| number(node, "gid")).transpose()?,
+                kind,
+            })
+        })
+        .collect()
+}
+
+/// Parses `points="0,0 16,0 16,16"`.
+fn points(text: &str) -> Result<Vec<Point2<f64>>, TiledError> {
+    text.split_whitespace()
+        .map(|pair| {
+            let mut coordinates = pair.split(',').map(f64::from_str);
+            match (coordinates.next(), coordinates.next(), coordinates.next()) {
+                (Some(Ok(x)), Some(Ok(y)), None) => Ok(Point2::new(x, y)),
+                _ => Err(TiledError::Invalid(format!("bad point {:?}", pair))),
+            }
+        })
+        .collect()
+}
+
+/// Tiled 1.9 renamed `type` to `class`.
+fn class(node: Node) -> String {
+    node.attribute("class").or_else(|| node.attribute("type")).unwrap_or("").to_string()
+}
+
+fn number<N: FromStr>(node: Node, name: &str) -> Result<N, TiledError> {
+    let value = node
+        .attribute(name)
+        .ok_or_else(|| TiledError::Invalid(format!("<{}> has no {}", node.tag_name().name(), name)))?;
+    value
+        .trim()
+        .parse()
+        .map_err(|_| TiledError::Invalid(format!("{}={:?} on <{}> isn't a number", name, value, node.tag_name().name())))
+}
+
+fn number_or<N: FromStr>(node: Node, name: &str, default: N) -> Result<N, TiledError> {
+    match node.attribute(name) {
+        Some(_) => number(node, name),
+        None => Ok(default),
+    }
+}
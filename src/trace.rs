@@ -0,0 +1,132 @@
+//! Collision shapes traced from the alpha channel of sprites.
+use crate::scalar::{cross, Real};
+use crate::shape::{ramer_douglas_peucker, GenericShape};
+#[cfg(feature = "amethyst")]
+use crate::shape::{ShapeKind, ShapePrefab};
+use nalgebra::{Point2, Vector2};
+use std::collections::BTreeMap;
+
+/// Turns the opaque pixels of RGBA images into convex `Shape`s.
+///
+/// Marching squares follows the border between opaque and transparent pixels, cutting pixel corners
+/// diagonally. The outline is then simplified and split into convex pieces. Every separate opaque
+/// region gets its own pieces, and holes are filled in.
+#[derive(Debug, Clone)]
+pub struct SpriteTracer {
+    /// Pixels with at least this alpha count as solid.
+    pub alpha_threshold: u8,
+    /// How far, in pixels, the simplified outline may stray from the traced one.
+    pub tolerance: f64,
+    /// Puts the origin in the middle of the sprite, where amethyst draws it, instead of its top left corner.
+    pub centered: bool,
+    /// Image rows run downwards. Set this to negate every y coordinate for a y up world.
+    pub flip_y: bool,
+}
+impl Default for SpriteTracer {
+    fn default() -> Self {
+        SpriteTracer {
+            alpha_threshold: 128,
+            tolerance: 1.,
+            centered: false,
+            flip_y: false,
+        }
+    }
+}
+impl SpriteTracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Traces a whole image, given as `width * height` RGBA pixels, row by row.
+    pub fn trace<T: Real>(&self, rgba: &[u8], width: usize, height: usize) -> Vec<GenericShape<T>> {
+        self.trace_sprite(rgba, width, (0, 0, width, height))
+    }
+    /// Traces one sprite of a sheet that is `sheet_width` pixels wide. The sprite is given as
+    /// `(x, y, width, height)` in pixels, and the shapes are relative to its top left corner.
+    pub fn trace_sprite<T: Real>(&self, rgba: &[u8], sheet_width: usize, sprite: (usize, usize, usize, usize)) -> Vec<GenericShape<T>> {
+        let (left, top, width, height) = sprite;
+        let solid = |x: i64, y: i64| {
+            if x < 0 || y < 0 || x >= width as i64 || y >= height as i64 {
+                return false;
+            }
+            let alpha = ((top + y as usize) * sheet_width + left + x as usize) * 4 + 3;
+            rgba.get(alpha).is_some_and(|&alpha| alpha >= self.alpha_threshold)
+        };
+        let origin = if self.centered { Vector2::new(width as f64, height as f64) / 2. } else { Vector2::zeros() };
+        let sign = if self.flip_y { -1. } else { 1. };
+        outlines(solid, width as i64, height as i64)
+            .into_iter()
+            .flat_map(|outline| GenericShape::convex_decomposition(ramer_douglas_peucker(&outline, self.tolerance)))
+            .map(|piece| {
+                GenericShape::new(piece.iter_points().map(|point| {
+                    let point = point - origin;
+                    Point2::new(T::from_f64(point.x), T::from_f64(sign * point.y))
+                }))
+            })
+            .collect()
+    }
+    /// Like `trace`, but as prefabs, one for each convex piece.
+    #[cfg(feature = "amethyst")]
+    pub fn trace_prefabs(&self, rgba: &[u8], width: usize, height: usize) -> Vec<ShapePrefab> {
+        self.trace::<f32>(rgba, width, height)
+            .into_iter()
+            .map(|shape| ShapePrefab {
+                shape: Some(ShapeKind::Polygon { points: shape.iter_points().cloned().collect() }),
+                ..ShapePrefab::default()
+            })
+            .collect()
+    }
+}
+
+/// Marching squares over the pixel centers. Returns the outer outline of every solid region, in pixels.
+fn outlines<F: Fn(i64, i64) -> bool>(solid: F, width: i64, height: i64) -> Vec<Vec<Point2<f64>>> {
+    // Outline points lie halfway between two pixel centers, so they are kept as the sum of both pixel positions.
+    let mut next = BTreeMap::new();
+    for y in -1..height {
+        for x in -1..width {
+            let corners = [(x, y), (x + 1, y), (x + 1, y + 1), (x, y + 1)];
+            let inside = corners.map(|(x, y)| solid(x, y));
+            let crossing = |edge: usize| {
+                let (a, b) = (corners[edge], corners[(edge + 1) % 4]);
+                (a.0 + b.0, a.1 + b.1)
+            };
+            let edges: Vec<usize> = (0..4).filter(|&edge| inside[edge] != inside[(edge + 1) % 4]).collect();
+            let pairs: Vec<(usize, usize)> = match edges.len() {
+                2 => vec![(edges[0], edges[1])],
+                // Two solid pixels touching diagonally stay connected, cutting off the empty corners.
+                4 => (0..4).filter(|&corner| !inside[corner]).map(|corner| ((corner + 3) % 4, corner)).collect(),
+                _ => Vec::new(),
+            };
+            let reference = match (0..4).find(|&corner| inside[corner]) {
+                Some(corner) => Vector2::new(2 * corners[corner].0, 2 * corners[corner].1),
+                None => continue,
+            };
+            for (a, b) in pairs {
+                let (a, b) = (crossing(a), crossing(b));
+                let (start, end) = (Vector2::new(a.0, a.1), Vector2::new(b.0, b.1));
+                // Walk with the solid pixels on the right, so outer outlines and holes wind in opposite directions.
+                if (end - start).x * (reference - start).y - (end - start).y * (reference - start).x > 0 {
+                    next.insert(a, b);
+                } else {
+                    next.insert(b, a);
+                }
+            }
+        }
+    }
+    let mut outlines = Vec::new();
+    while let Some((&start, _)) = next.iter().next() {
+        let mut outline = Vec::new();
+        let mut point = start;
+        while let Some(following) = next.remove(&point) {
+            outline.push(Point2::new(point.0 as f64 / 2. + 0.5, point.1 as f64 / 2. + 0.5));
+            point = following;
+        }
+        let area = outline
+            .iter()
+            .zip(outline.iter().cycle().skip(1))
+            .fold(0., |area, (a, b)| area + cross(&a.coords, &b.coords));
+        if area > 0. {
+            outlines.push(outline);
+        }
+    }
+    outlines
+}